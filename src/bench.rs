@@ -0,0 +1,173 @@
+use std::{
+    error::Error,
+    io::{stderr, Write},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use sigmars_garden_solver::{Generator, SolveOptions, SolveResult};
+
+use crate::Strategy;
+
+/// Results of solving a corpus of generated boards.
+#[derive(Debug, Default)]
+struct Report {
+    solved: usize,
+    unsolvable: usize,
+    timeouts: usize,
+    times: Vec<Duration>,
+    nodes: Vec<u64>,
+}
+
+impl Report {
+    fn boards(&self) -> usize {
+        self.solved + self.unsolvable + self.timeouts
+    }
+
+    fn rate(&self, count: usize) -> f64 {
+        count as f64 / self.boards() as f64
+    }
+
+    fn print_text(&self, strategy: &str) {
+        println!("strategy:   {strategy}");
+        println!("boards:     {}", self.boards());
+        println!(
+            "solved:     {} ({:.2}%)",
+            self.solved,
+            100.0 * self.rate(self.solved)
+        );
+        println!(
+            "unsolvable: {} ({:.2}%)",
+            self.unsolvable,
+            100.0 * self.rate(self.unsolvable)
+        );
+        println!(
+            "timeouts:   {} ({:.2}%)",
+            self.timeouts,
+            100.0 * self.rate(self.timeouts)
+        );
+        println!(
+            "time:       mean {:.3} ms, p50 {:.3} ms, p99 {:.3} ms",
+            1000.0 * mean(&self.times),
+            millis(percentile(&self.times, 0.5)),
+            millis(percentile(&self.times, 0.99)),
+        );
+        println!(
+            "nodes:      mean {:.0}, p50 {}, p99 {}, total {}",
+            mean(&self.nodes),
+            percentile(&self.nodes, 0.5),
+            percentile(&self.nodes, 0.99),
+            self.nodes.iter().sum::<u64>(),
+        );
+    }
+
+    /// Prints the report as a single line of JSON, which is easy to diff between runs.
+    fn print_json(&self, strategy: &str) {
+        println!(
+            concat!(
+                "{{",
+                r#""strategy":"{}","boards":{},"solved":{},"unsolvable":{},"timeouts":{},"#,
+                r#""solve_rate":{},"timeout_rate":{},"#,
+                r#""time_mean_ms":{:.3},"time_p50_ms":{:.3},"time_p99_ms":{:.3},"#,
+                r#""nodes_mean":{:.1},"nodes_p50":{},"nodes_p99":{},"nodes_total":{}"#,
+                "}}"
+            ),
+            strategy,
+            self.boards(),
+            self.solved,
+            self.unsolvable,
+            self.timeouts,
+            self.rate(self.solved),
+            self.rate(self.timeouts),
+            1000.0 * mean(&self.times),
+            millis(percentile(&self.times, 0.5)),
+            millis(percentile(&self.times, 0.99)),
+            mean(&self.nodes),
+            percentile(&self.nodes, 0.5),
+            percentile(&self.nodes, 0.99),
+            self.nodes.iter().sum::<u64>(),
+        );
+    }
+}
+
+pub(crate) fn bench(
+    seed: u64,
+    count: usize,
+    solvable: bool,
+    options: SolveOptions,
+    strategies: &[Strategy],
+    json: bool,
+) -> Result<ExitCode, Box<dyn Error>> {
+    if count == 0 {
+        return Err("at least one board is required".into());
+    }
+
+    let mut generator = Generator::new(seed).solvable(solvable);
+    let boards = (0..count).map(|_| generator.generate()).collect::<Vec<_>>();
+
+    for (i, &strategy) in strategies.iter().enumerate() {
+        let solver = strategy.solver();
+        let mut report = Report::default();
+
+        for (j, &board) in boards.iter().enumerate() {
+            let start = Instant::now();
+            let (result, stats) = solver.solve(board.into(), options);
+            report.times.push(start.elapsed());
+            report.nodes.push(stats.nodes);
+
+            match result {
+                SolveResult::Solution(_) => report.solved += 1,
+                SolveResult::Unsolvable => report.unsolvable += 1,
+                SolveResult::Timeout => report.timeouts += 1,
+            }
+
+            eprint!("\rSolved {}/{count} boards ({})...", j + 1, strategy.name());
+            stderr().flush()?;
+        }
+        eprintln!();
+
+        if json {
+            report.print_json(strategy.name());
+        } else {
+            if i != 0 {
+                println!();
+            }
+            report.print_text(strategy.name());
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Allows computing statistics for both durations (in seconds) and node counts.
+trait Sample: Copy + Ord {
+    fn to_f64(self) -> f64;
+}
+
+impl Sample for Duration {
+    fn to_f64(self) -> f64 {
+        self.as_secs_f64()
+    }
+}
+
+impl Sample for u64 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+fn mean<T: Sample>(samples: &[T]) -> f64 {
+    samples.iter().map(|sample| sample.to_f64()).sum::<f64>() / samples.len() as f64
+}
+
+/// Uses the nearest-rank method.
+fn percentile<T: Sample>(samples: &[T], fraction: f64) -> T {
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
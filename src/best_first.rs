@@ -0,0 +1,306 @@
+//! A best-first search that expands the most promising position first, no matter where it is in
+//! the search tree.
+//!
+//! Positions are scored by how many elements were removed, how many of the remaining ones are free,
+//! how much salt is left over after pairing up the cardinal elements, and whether the next metal
+//! can be removed right away. Unlike the depth-first search, this needs to keep all positions that
+//! were reached but not expanded yet, so their number is limited like in a beam search.
+
+use std::{cmp::Ordering, collections::BinaryHeap, time::Instant};
+
+use crate::{
+    bitboard::BitBoard,
+    board::Step,
+    element::Element,
+    rules::RuleSet,
+    search::{DeadPositions, DEAD_POSITIONS_MEMORY},
+    solver::{GamePosition, SolveOptions, SolveResult, SolveStats, Solver},
+};
+
+/// How many positions to expand between checking the timeout.
+const TIMEOUT_CHECK_INTERVAL: u64 = 256;
+
+/// Searches for a solution by always expanding the position with the best score.
+///
+/// Runs on a single thread, ignoring [`SolveOptions::threads`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BestFirst {
+    /// How many positions to keep around for expanding later, dropping the worst ones beyond that.
+    ///
+    /// Once positions were dropped, the search can no longer prove a board to be unsolvable, so
+    /// running out of positions counts as a [timeout](SolveResult::Timeout) instead.
+    pub beam_width: usize,
+}
+
+impl Default for BestFirst {
+    fn default() -> Self {
+        Self {
+            beam_width: 100_000,
+        }
+    }
+}
+
+impl Solver for BestFirst {
+    fn solve(&self, position: GamePosition, options: SolveOptions) -> (SolveResult, SolveStats) {
+        let start = Instant::now();
+        let rules = position.rules();
+        let mut stats = SolveStats::default();
+        // Positions that were already reached, so they are not expanded again.
+        let seen = DeadPositions::with_memory(DEAD_POSITIONS_MEMORY);
+        // The step leading to each node along with the node it was made from.
+        let mut parents = Vec::<Option<(usize, Step)>>::new();
+        let mut open = BinaryHeap::new();
+        let mut dropped = false;
+
+        let initial_board = BitBoard::from(position.board());
+        parents.push(None);
+        open.push(Node {
+            score: score(&initial_board, &rules),
+            depth: 0,
+            id: 0,
+            board: initial_board,
+        });
+
+        let result = loop {
+            if stats.nodes % TIMEOUT_CHECK_INTERVAL == 0 && start.elapsed() >= options.timeout {
+                break SolveResult::Timeout;
+            }
+            let Some(node) = open.pop() else {
+                break if dropped {
+                    SolveResult::Timeout
+                } else {
+                    SolveResult::Unsolvable
+                };
+            };
+            if node.board.is_solved() {
+                break SolveResult::Solution(path(&parents, node.id));
+            }
+
+            let steps = node.board.valid_steps(&rules);
+            stats.expanded(node.depth, steps.len());
+            for step in steps {
+                let mut board = node.board;
+                board.remove(step.0[0]);
+                board.remove(step.0[1]);
+
+                let hash = board.occupied_hash();
+                if seen.contains(hash) {
+                    continue;
+                }
+                seen.insert(hash);
+                if !options.pruning.is_feasible(&board, &rules) {
+                    stats.pruned += 1;
+                    continue;
+                }
+
+                let depth = node.depth + 1;
+                stats.max_depth = stats.max_depth.max(depth);
+                parents.push(Some((node.id, step)));
+                open.push(Node {
+                    score: score(&board, &rules),
+                    depth,
+                    id: parents.len() - 1,
+                    board,
+                });
+            }
+
+            if open.len() > 2 * self.beam_width {
+                let mut nodes = open.into_sorted_vec();
+                nodes.drain(..nodes.len() - self.beam_width);
+                compact(&mut parents, &mut nodes);
+                open = nodes.into();
+                dropped = true;
+            }
+        };
+
+        stats.elapsed = start.elapsed();
+        (result, stats)
+    }
+}
+
+/// A position waiting to be expanded, ordered by score.
+struct Node {
+    score: i32,
+    depth: usize,
+    id: usize,
+    board: BitBoard,
+}
+
+impl Node {
+    /// Ties are broken in favor of deeper and then newer positions, which makes the search dive
+    /// towards a solution instead of widening.
+    fn key(&self) -> (i32, usize, usize) {
+        (self.score, self.depth, self.id)
+    }
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// How promising a position is, with higher being better.
+fn score(board: &BitBoard, rules: &RuleSet) -> i32 {
+    let count = |element| board.mask(element).count_ones() as i32;
+    let remaining = Element::ALL.into_iter().map(count).sum::<i32>();
+    let free = board.free(rules.free_neighbors);
+
+    // Salt that is not needed for cardinal elements with an odd count can be spent freely.
+    let odd_cardinals = Element::CARDINALS
+        .into_iter()
+        .filter(|&element| count(element) % 2 == 1)
+        .count() as i32;
+    let spare_salt = count(Element::Salt) - odd_cardinals;
+
+    // A metal that is already free does not hold up the others.
+    let next_ordered = Element::ALL
+        .into_iter()
+        .find(|&element| rules.is_ordered(element) && board.mask(element) != 0);
+    let metal_free = next_ordered.is_some_and(|element| board.mask(element) & free != 0);
+
+    -4 * remaining + free.count_ones() as i32 + 2 * spare_salt + 4 * i32::from(metal_free)
+}
+
+/// Drops the parent links that do not lead to any of the nodes, renumbering the nodes and the
+/// links that are left in the same order so ties are still broken the same way.
+fn compact(parents: &mut Vec<Option<(usize, Step)>>, nodes: &mut [Node]) {
+    let mut kept = vec![false; parents.len()];
+    for node in nodes.iter() {
+        let mut id = node.id;
+        while !kept[id] {
+            kept[id] = true;
+            match parents[id] {
+                Some((parent, _)) => id = parent,
+                None => break,
+            }
+        }
+    }
+
+    // Parents always come before their children, so they are renumbered first.
+    let mut new_ids = vec![usize::MAX; parents.len()];
+    let mut compacted = Vec::new();
+    for (id, link) in parents.iter().enumerate() {
+        if kept[id] {
+            new_ids[id] = compacted.len();
+            compacted.push(link.map(|(parent, step)| (new_ids[parent], step)));
+        }
+    }
+    for node in nodes {
+        node.id = new_ids[node.id];
+    }
+    *parents = compacted;
+}
+
+/// The steps leading to the node, in order.
+fn path(parents: &[Option<(usize, Step)>], mut id: usize) -> Vec<Step> {
+    let mut steps = Vec::new();
+    while let Some((parent, step)) = parents[id] {
+        steps.push(step);
+        id = parent;
+    }
+    steps.reverse();
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{board::Board, generator::Generator, index::SigIndex, verify::verify};
+
+    use super::*;
+
+    #[test]
+    fn finds_valid_solutions() {
+        let mut generator = Generator::new(6).solvable(true);
+        let options = SolveOptions {
+            timeout: Duration::from_secs(60),
+            ..SolveOptions::default()
+        };
+        for _ in 0..5 {
+            let board = generator.generate();
+            let (result, stats) = BestFirst::default().solve(board.into(), options);
+            let SolveResult::Solution(steps) = result else {
+                panic!("generated board should be solvable");
+            };
+            assert_eq!(verify(board.board(), &steps), Ok(()));
+            assert!(stats.nodes > 0);
+        }
+    }
+
+    #[test]
+    fn proves_unsolvable() {
+        // Fire cannot be paired with itself, so one of the fires is always left over.
+        let rules = RuleSet::STANDARD.pair(Element::Fire, Element::Fire, false);
+        let board = Board::with_elements(&[
+            (0, 0, Element::Fire),
+            (0, 1, Element::Fire),
+            (5, 5, Element::Salt),
+        ]);
+        let position = GamePosition::with_rules(board, rules).unwrap();
+        let (result, _) = BestFirst::default().solve(position, SolveOptions::default());
+        assert_eq!(result, SolveResult::Unsolvable);
+    }
+
+    #[test]
+    fn compacting_keeps_the_paths_of_the_nodes() {
+        let step = |n| Step([SigIndex::new(n).unwrap(), SigIndex::new(n + 1).unwrap()]);
+        let node = |id| Node {
+            score: 0,
+            depth: 0,
+            id,
+            board: BitBoard::from(Board::new()),
+        };
+        // 0 has children 1 and 2, 1 has 3 and 2 has 4 and 5.
+        let mut parents = vec![
+            None,
+            Some((0, step(1))),
+            Some((0, step(2))),
+            Some((1, step(3))),
+            Some((2, step(4))),
+            Some((2, step(5))),
+        ];
+        let mut nodes = [node(5), node(3)];
+        let paths = |parents: &[_], nodes: &[Node]| {
+            nodes
+                .iter()
+                .map(|node| path(parents, node.id))
+                .collect::<Vec<_>>()
+        };
+        let expected = paths(&parents, &nodes);
+
+        compact(&mut parents, &mut nodes);
+        assert_eq!(parents.len(), 5);
+        assert_eq!([nodes[0].id, nodes[1].id], [4, 3]);
+        assert_eq!(paths(&parents, &nodes), expected);
+    }
+
+    #[test]
+    fn a_narrow_beam_still_finds_solutions() {
+        let board = Generator::new(6).solvable(true).generate();
+        let options = SolveOptions {
+            timeout: Duration::from_secs(60),
+            ..SolveOptions::default()
+        };
+        let (result, _) = BestFirst { beam_width: 50 }.solve(board.into(), options);
+        let SolveResult::Solution(steps) = result else {
+            panic!("the board should be solved despite pruning");
+        };
+        assert_eq!(verify(board.board(), &steps), Ok(()));
+    }
+}
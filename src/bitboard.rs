@@ -0,0 +1,268 @@
+use crate::{
+    board::{Board, Step},
+    element::Element,
+    index::SigIndex,
+    rules::RuleSet,
+};
+
+/// Shifts that move a bit to its adjacent position in clockwise order, starting at the right.
+///
+/// Since the 11x11 grid is larger than the hexagonal playing field, a shift can never wrap around
+/// from one edge of the playing field onto a position on the other side.
+const ADJACENT_SHIFTS: [i32; 6] = [11, 12, 1, -11, -12, -1];
+
+/// All bits that correspond to a position on the playing field.
+const FIELD: u128 = {
+    let mut field = 0;
+    let mut index = 0;
+    while index < 121 {
+        let (row, col) = (index / 11 - 5, index % 11 - 5);
+        if row - col <= 5 && col - row <= 5 {
+            field |= 1 << index;
+        }
+        index += 1;
+    }
+    field
+};
+
+/// A [`Board`] stored as occupancy masks with one bit per position.
+///
+/// Besides a mask of all occupied positions, there is one mask per [`Element`], which allows
+/// computing e.g. which positions are free for the whole board at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct BitBoard {
+    occupied: u128,
+    elements: [u128; 14],
+}
+
+impl BitBoard {
+    pub(crate) fn get(&self, index: SigIndex) -> Option<Element> {
+        let bit = 1 << index.to_bit();
+        (self.occupied & bit != 0)
+            .then(|| {
+                Element::ALL
+                    .into_iter()
+                    .find(|&element| self.mask(element) & bit != 0)
+            })
+            .flatten()
+    }
+
+    /// Removes the element at the given position.
+    pub(crate) fn remove(&mut self, index: SigIndex) {
+        let bit = 1 << index.to_bit();
+        self.occupied &= !bit;
+        for mask in &mut self.elements {
+            *mask &= !bit;
+        }
+    }
+
+    /// Puts back whatever element `original` has at the given position.
+    pub(crate) fn restore(&mut self, index: SigIndex, original: &BitBoard) {
+        let bit = 1 << index.to_bit();
+        self.occupied |= original.occupied & bit;
+        for (mask, original_mask) in self.elements.iter_mut().zip(original.elements) {
+            *mask |= original_mask & bit;
+        }
+    }
+
+    pub(crate) fn mask(&self, element: Element) -> u128 {
+        self.elements[element as usize - 1]
+    }
+
+    pub(crate) fn is_solved(&self) -> bool {
+        self.occupied == 0
+    }
+
+    /// A mask of all occupied positions that have at least `free_neighbors` consecutive empty
+    /// neighbors.
+    pub(crate) fn free(&self, free_neighbors: u8) -> u128 {
+        free_mask(self.occupied, free_neighbors)
+    }
+
+    /// Returns a list of all possible moves that can be made in the current state.
+    ///
+    /// The moves are sorted by likelihood of not leading to a rollback, with the most likely last,
+    /// so that popping off the end of the list is more efficient.
+    pub(crate) fn valid_steps(&self, rules: &RuleSet) -> Vec<Step> {
+        let free = self.free(rules.free_neighbors);
+        let free_of = |element| self.mask(element) & free;
+
+        // Of the elements that have to be removed in order, only the first one left is available.
+        let next_ordered = Element::ALL
+            .into_iter()
+            .find(|&element| rules.is_ordered(element) && self.mask(element) != 0);
+        let unordered = || {
+            Element::ALL
+                .into_iter()
+                .filter(|&element| !rules.is_ordered(element))
+        };
+
+        let mut steps = Vec::<Step>::new();
+
+        // Pairs with the element itself come before pairs with later elements, e.g. salt with
+        // itself before salt with cardinal elements, which are most often a waste of salt.
+        for element in unordered() {
+            if rules.is_removed_alone(element) {
+                steps.extend(bits(free_of(element)).map(|index| Step([index, index])));
+            }
+            if rules.can_pair(element, element) {
+                self_combinations(&mut steps, free_of(element));
+            }
+            for index in bits(free_of(element)) {
+                for other in
+                    unordered().filter(|&other| other > element && rules.can_pair(element, other))
+                {
+                    steps
+                        .extend(bits(free_of(other)).map(|other_index| Step([other_index, index])));
+                }
+            }
+        }
+
+        // Ordered elements block everything after them, so removing them is the most promising.
+        if let Some(element) = next_ordered {
+            for index in bits(free_of(element)) {
+                for other in unordered().filter(|&other| rules.can_pair(element, other)) {
+                    steps
+                        .extend(bits(free_of(other)).map(|other_index| Step([index, other_index])));
+                }
+            }
+            if rules.can_pair(element, element) {
+                self_combinations(&mut steps, free_of(element));
+            }
+            if rules.is_removed_alone(element) {
+                steps.extend(bits(free_of(element)).map(|index| Step([index, index])));
+            }
+        }
+
+        steps
+    }
+
+    /// A hash of the occupied positions.
+    ///
+    /// Since elements are only ever removed, this is enough to tell apart all positions that
+    /// can be reached from the same initial board.
+    pub(crate) fn occupied_hash(&self) -> u64 {
+        const SEED: u64 = 0x517c_c1b7_2722_0a95;
+
+        let low = self.occupied as u64;
+        let high = (self.occupied >> 64) as u64;
+        (low.wrapping_mul(SEED).rotate_left(5) ^ high).wrapping_mul(SEED)
+    }
+}
+
+impl From<Board> for BitBoard {
+    fn from(board: Board) -> Self {
+        let mut bit_board = Self {
+            occupied: 0,
+            elements: [0; 14],
+        };
+        for index in SigIndex::all() {
+            if let Some(element) = board.get(index) {
+                let bit = 1 << index.to_bit();
+                bit_board.occupied |= bit;
+                bit_board.elements[element as usize - 1] |= bit;
+            }
+        }
+        debug_assert_eq!(bit_board.occupied & !FIELD, 0);
+        bit_board
+    }
+}
+
+impl From<BitBoard> for Board {
+    fn from(bit_board: BitBoard) -> Self {
+        let mut board = Board::new();
+        for index in bits(bit_board.occupied) {
+            board.set(index, bit_board.get(index));
+        }
+        board
+    }
+}
+
+/// A mask of all positions in `occupied` that have at least `free_neighbors` consecutive empty
+/// neighbors.
+pub(crate) fn free_mask(occupied: u128, free_neighbors: u8) -> u128 {
+    let empty_neighbors = ADJACENT_SHIFTS.map(|shift| {
+        let occupied_neighbors = if shift > 0 {
+            occupied >> shift
+        } else {
+            occupied << -shift
+        };
+        !occupied_neighbors
+    });
+
+    let any_consecutive_empty = (0..6).fold(0, |free, direction| {
+        free | (0..usize::from(free_neighbors)).fold(!0, |empty, offset| {
+            empty & empty_neighbors[(direction + offset) % 6]
+        })
+    });
+
+    occupied & any_consecutive_empty
+}
+
+/// A mask of all positions that are adjacent to any position in `mask`.
+pub(crate) fn adjacent_mask(mask: u128) -> u128 {
+    ADJACENT_SHIFTS.into_iter().fold(0, |adjacent, shift| {
+        adjacent
+            | if shift > 0 {
+                mask << shift
+            } else {
+                mask >> -shift
+            }
+    }) & FIELD
+}
+
+/// Iterates over all set bits in ascending order, which matches the order of [`SigIndex::all`].
+pub(crate) fn bits(mut mask: u128) -> impl Iterator<Item = SigIndex> + Clone {
+    std::iter::from_fn(move || {
+        (mask != 0).then(|| {
+            let index = lowest_bit(mask);
+            mask &= mask - 1;
+            index
+        })
+    })
+}
+
+fn lowest_bit(mask: u128) -> SigIndex {
+    SigIndex::from_bit(mask.trailing_zeros())
+}
+
+/// Pushes steps for all pairs of positions in the mask.
+fn self_combinations(steps: &mut Vec<Step>, mask: u128) {
+    for first in bits(mask) {
+        let rest = mask & !((2 << first.to_bit()) - 1);
+        steps.extend(bits(rest).map(|second| Step([first, second])));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::index::SigCoord;
+
+    use super::*;
+
+    #[test]
+    fn only_corners_of_full_board_are_free() {
+        let mut board = Board::new();
+        for index in SigIndex::all() {
+            board.set(index, Some(Element::Salt));
+        }
+
+        let free = bits(BitBoard::from(board).free(RuleSet::STANDARD.free_neighbors))
+            .map(|index| {
+                let coord = SigCoord::from(index);
+                (coord.row(), coord.col())
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(free, [(-5, -5), (-5, 0), (0, -5), (0, 5), (5, 0), (5, 5)]);
+    }
+
+    #[test]
+    fn board_conversion() {
+        let mut board = Board::new();
+        for (index, element_index) in SigIndex::all().zip((0..15).cycle()) {
+            board.set(index, Element::from_index(element_index));
+        }
+        assert_eq!(Board::from(BitBoard::from(board)), board);
+    }
+}
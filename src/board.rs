@@ -1,21 +1,30 @@
-use itertools::Itertools;
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
 
 use crate::{
     element::Element,
     index::{SigCoord, SigIndex},
+    rules::RuleSet,
 };
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) struct Board {
+/// The state of all 91 positions of the playing field.
+///
+/// Boards are ordered by the elements on each position, in the order of their [`SigIndex`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Board {
     bits: [u8; 46],
 }
 
 impl Board {
-    pub(crate) const fn new() -> Self {
+    /// Creates an empty board.
+    pub const fn new() -> Self {
         Self { bits: [0; 46] }
     }
 
-    pub(crate) fn get(self, index: impl Into<SigIndex>) -> Option<Element> {
+    /// Returns the element at the given position, if any.
+    pub fn get(self, index: impl Into<SigIndex>) -> Option<Element> {
         let index = index.into().to_usize();
         let bits = self.bits[index / 2];
         Element::from_index(if index % 2 == 0 {
@@ -25,7 +34,8 @@ impl Board {
         })
     }
 
-    pub(crate) fn set(&mut self, index: impl Into<SigIndex>, element: Option<Element>) {
+    /// Places an element at the given position or clears it when [`None`].
+    pub fn set(&mut self, index: impl Into<SigIndex>, element: Option<Element>) {
         let index = index.into().to_usize();
         let bits = &mut self.bits[index / 2];
         let element_index = Element::to_index(element);
@@ -36,119 +46,74 @@ impl Board {
         };
     }
 
-    fn is_free(self, coord: impl Into<SigCoord>) -> bool {
-        let adjacent_elements = coord
-            .into()
-            .adjacent_cw()
-            .map(|coord| coord.and_then(|coord| self.get(coord)));
+    /// Clears both positions of the given step, without checking whether the step is valid.
+    pub fn remove(&mut self, Step([index1, index2]): Step) {
+        self.set(index1, None);
+        self.set(index2, None);
+    }
 
-        matches!(
-            adjacent_elements,
-            [None, None, None, _, _, _]
-                | [_, None, None, None, _, _]
-                | [_, _, None, None, None, _]
-                | [_, _, _, None, None, None]
-                | [None, _, _, _, None, None]
-                | [None, None, _, _, _, None]
-        )
+    /// Returns the board after making the step under the [standard rules](RuleSet::STANDARD), or
+    /// why the step cannot be made.
+    ///
+    /// See [`RuleSet::apply`].
+    pub fn apply(self, step: Step) -> Result<Board, StepError> {
+        RuleSet::STANDARD.apply(self, step)
     }
 
-    fn free_elements(self) -> impl Iterator<Item = SigIndex> + Clone {
-        SigIndex::all().filter(move |&index| self.get(index).is_some() && self.is_free(index))
+    /// Whether the position has three consecutive empty neighbors, counting positions outside of
+    /// the playing field as empty.
+    ///
+    /// This does not check whether the position itself is occupied.
+    pub fn is_free(self, index: impl Into<SigIndex>) -> bool {
+        RuleSet::STANDARD.is_free(self, index)
     }
 
-    pub(crate) fn is_valid_initial_state(self) -> bool {
+    /// Reverts a step by putting back the elements it removed, in the order of its positions.
+    ///
+    /// For a step removing gold, both elements are [`Element::Gold`].
+    pub fn undo(self, Step([index1, index2]): Step, [element1, element2]: [Element; 2]) -> Board {
+        let mut board = self;
+        board.set(index1, Some(element1));
+        board.set(index2, Some(element2));
+        board
+    }
+
+    /// Whether the board contains exactly the elements of a freshly dealt game.
+    pub fn is_valid_initial_state(self) -> bool {
+        RuleSet::STANDARD.is_valid_initial_state(self)
+    }
+
+    /// Whether the remaining elements could be left over after removing pairs from a freshly
+    /// dealt game under the [standard rules](RuleSet::STANDARD).
+    ///
+    /// Only the number of each element is checked, not whether the removed elements could have
+    /// actually been free.
+    pub fn is_consistent(self) -> bool {
+        RuleSet::STANDARD.is_consistent(self)
+    }
+
+    /// The number of each element on the board, ordered like the elements themselves.
+    pub(crate) fn element_counts(self) -> [u8; 14] {
         SigIndex::all().fold([0; 14], |mut counts, index| {
             let element_index = Element::to_index(self.get(index));
             if element_index != 0 {
                 counts[element_index as usize - 1] += 1;
             }
             counts
-        }) == [4, 8, 8, 8, 8, 4, 4, 5, 1, 1, 1, 1, 1, 1]
+        })
     }
 
-    /// Returns a list of all possible moves that can be made in the current state.
+    /// Returns a list of all possible moves that can be made in the current state under the
+    /// [standard rules](RuleSet::STANDARD).
     ///
     /// The moves are sorted by likelihood of not leading to a rollback, with the most likely last,
     /// so that popping off the end of the list is more efficient.
-    pub(crate) fn valid_steps(self) -> Vec<Step> {
-        let mut steps = Vec::<Step>::new();
-
-        steps.extend(self.self_combinations(Element::Salt));
-
-        steps.extend(
-            self.free_elements()
-                .filter(|&index| self.get(index) == Some(Element::Salt))
-                .flat_map(|salt_index| {
-                    [Element::Air, Element::Fire, Element::Water, Element::Earth]
-                        .into_iter()
-                        .flat_map(move |element| {
-                            self.free_elements()
-                                .filter(move |&index| self.get(index) == Some(element))
-                                .map(move |element_index| Step([element_index, salt_index]))
-                        })
-                }),
-        );
-
-        steps.extend(
-            [Element::Air, Element::Fire, Element::Water, Element::Earth]
-                .into_iter()
-                .flat_map(|element| self.self_combinations(element)),
-        );
-
-        steps.extend(
-            self.free_elements()
-                .filter(|&mors_index| (self.get(mors_index) == Some(Element::Mors)))
-                .flat_map(|mors_index| {
-                    self.free_elements()
-                        .filter(|&vitae_index| (self.get(vitae_index) == Some(Element::Vitae)))
-                        .map(move |vitae_index| Step([mors_index, vitae_index]))
-                }),
-        );
-
-        let metal_steps = [
-            Element::Lead,
-            Element::Tin,
-            Element::Iron,
-            Element::Copper,
-            Element::Silver,
-        ]
-        .into_iter()
-        .find_map(|metal| {
-            SigIndex::all()
-                .find(|index| self.get(*index) == Some(metal))
-                .map(|metal_index| {
-                    self.is_free(metal_index).then(|| {
-                        self.free_elements()
-                            .filter(|index| self.get(*index) == Some(Element::Quicksilver))
-                            .map(move |quicksilver_index| Step([metal_index, quicksilver_index]))
-                    })
-                })
-        });
-
-        if let Some(metal_steps) = metal_steps {
-            if let Some(metal_steps) = metal_steps {
-                steps.extend(metal_steps);
-            }
-        } else {
-            steps.extend(
-                self.free_elements()
-                    .find(|&index| self.get(index) == Some(Element::Gold))
-                    .map(|gold_index| Step([gold_index, gold_index])),
-            );
-        }
-
-        steps
+    pub fn valid_steps(self) -> Vec<Step> {
+        RuleSet::STANDARD.valid_steps(self)
     }
 
-    fn self_combinations(self, element: Element) -> impl Iterator<Item = Step> + Clone {
-        self.free_elements()
-            .filter(move |&index| self.get(index) == Some(element))
-            .tuple_combinations::<(_, _)>()
-            .map(|indices| Step([indices.0, indices.1]))
-    }
-
-    pub(crate) fn is_solved(self) -> bool {
+    /// Whether all elements have been removed.
+    pub fn is_solved(self) -> bool {
         self == Board::new()
     }
 }
@@ -159,5 +124,159 @@ impl Default for Board {
     }
 }
 
+#[cfg(test)]
+impl Board {
+    /// Creates a board with the elements at the given `(row, col)` coordinates, for writing small
+    /// boards in tests.
+    pub(crate) fn with_elements(elements: &[(i8, i8, Element)]) -> Self {
+        let mut board = Self::new();
+        for &(row, col, element) in elements {
+            board.set(SigCoord::new(row, col).unwrap(), Some(element));
+        }
+        board
+    }
+}
+
+/// A single move, removing the elements at both positions.
+///
+/// Gold is removed on its own, in which case both positions are the same.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step(pub [SigIndex; 2]);
+
+/// Why a [`Step`] cannot be made on a [`Board`].
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) struct Step(pub(crate) [SigIndex; 2]);
+pub enum StepError {
+    /// There is no element at the position.
+    Empty(SigIndex),
+    /// The element at the position is blocked by its neighbors.
+    NotFree(SigIndex),
+    /// The two elements cannot be removed together.
+    Mismatch(Element, Element),
+    /// Another metal has to be removed first, or another element that is
+    /// [ordered](RuleSet::is_ordered) under other rules.
+    WrongMetalOrder(Element),
+    /// Gold can only be removed once all other metals are gone, which also applies to other
+    /// elements that are [removed alone](RuleSet::is_removed_alone) and ordered.
+    GoldNotLast,
+}
+
+impl Display for StepError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Empty(index) => write!(f, "no element at {}", SigCoord::from(*index)),
+            Self::NotFree(index) => write!(f, "element at {} is not free", SigCoord::from(*index)),
+            Self::Mismatch(element1, element2) => {
+                write!(f, "{element1:?} cannot be paired with {element2:?}")
+            }
+            Self::WrongMetalOrder(metal) => write!(f, "{metal:?} has to be removed first"),
+            Self::GoldNotLast => write!(f, "gold has to be removed last"),
+        }
+    }
+}
+
+impl Error for StepError {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{generator::Generator, solver::SolveResult};
+
+    use super::*;
+
+    #[test]
+    fn positions_during_solution_are_consistent() {
+        let initial_board = Generator::new(5).solvable(true).generate();
+        let SolveResult::Solution(steps) = initial_board.solve(Duration::from_secs(60)) else {
+            panic!("generated board should be solvable");
+        };
+
+        let mut board = initial_board.board();
+        assert!(board.is_consistent());
+        for step in steps {
+            board = board.apply(step).unwrap();
+            assert!(board.is_consistent());
+        }
+        assert!(board.is_solved());
+    }
+
+    #[test]
+    fn invalid_steps_are_rejected() {
+        let at = |row, col| SigIndex::from(SigCoord::new(row, col).unwrap());
+        let mut board = Board::new();
+        for (index, element) in [
+            (at(0, 0), Element::Fire),
+            (at(0, 2), Element::Water),
+            (at(0, 4), Element::Salt),
+            (at(2, 0), Element::Tin),
+            (at(2, 2), Element::Lead),
+            (at(2, 4), Element::Quicksilver),
+            (at(-2, 0), Element::Quicksilver),
+            (at(-2, 2), Element::Gold),
+        ] {
+            board.set(index, Some(element));
+        }
+
+        let step = |index1, index2| board.apply(Step([index1, index2]));
+        assert_eq!(step(at(0, 0), at(4, 4)), Err(StepError::Empty(at(4, 4))));
+        assert_eq!(
+            step(at(0, 0), at(0, 2)),
+            Err(StepError::Mismatch(Element::Fire, Element::Water))
+        );
+        assert_eq!(
+            step(at(2, 0), at(2, 4)),
+            Err(StepError::WrongMetalOrder(Element::Lead))
+        );
+        assert_eq!(step(at(-2, 2), at(-2, 2)), Err(StepError::GoldNotLast));
+
+        let fire_and_salt = Step([at(0, 0), at(0, 4)]);
+        let next_board = board.apply(fire_and_salt).unwrap();
+        assert_eq!(next_board.get(at(0, 0)), None);
+        assert_eq!(
+            next_board.undo(fire_and_salt, [Element::Fire, Element::Salt]),
+            board
+        );
+
+        // Air surrounded by air on all sides.
+        let mut blocked_board = board;
+        for (row, col) in [
+            (-3, -3),
+            (-3, -2),
+            (-3, -4),
+            (-2, -3),
+            (-4, -3),
+            (-2, -2),
+            (-4, -4),
+        ] {
+            blocked_board.set(at(row, col), Some(Element::Air));
+        }
+        assert_eq!(
+            blocked_board.apply(Step([at(-3, -3), at(-3, -2)])),
+            Err(StepError::NotFree(at(-3, -3)))
+        );
+    }
+
+    #[test]
+    fn inconsistent_positions() {
+        let board = Generator::new(5).generate().board();
+        let find = |element| {
+            SigIndex::all()
+                .find(|&index| board.get(index) == Some(element))
+                .unwrap()
+        };
+
+        let mut skipped_lead = board;
+        skipped_lead.remove(Step([find(Element::Tin), find(Element::Quicksilver)]));
+        assert!(!skipped_lead.is_consistent());
+
+        let mut unpaired_vitae = board;
+        unpaired_vitae.set(find(Element::Vitae), None);
+        assert!(!unpaired_vitae.is_consistent());
+
+        let mut unpaired_fire = board;
+        unpaired_fire.set(find(Element::Fire), None);
+        assert!(!unpaired_fire.is_consistent());
+        unpaired_fire.set(find(Element::Salt), None);
+        assert!(unpaired_fire.is_consistent());
+    }
+}
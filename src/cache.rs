@@ -0,0 +1,264 @@
+//! Remembering the outcome of solving boards across runs.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    board::{Board, Step},
+    solver::SolveResult,
+    symmetry::Symmetry,
+};
+
+/// The number of boards kept by default before the oldest ones are dropped.
+const DEFAULT_LIMIT: usize = 10_000;
+
+/// A file of known outcomes.
+///
+/// Boards are stored in their [canonical form](Board::canonical), so a board that is a rotated or
+/// mirrored image of one seen before is found as well. Only definite outcomes are stored, i.e.
+/// solutions and boards proven unsolvable, but not timeouts.
+///
+/// The cache is a text file with one board per line: the board in the compact
+/// [notation](crate::notation), the outcome (`solved` or `unsolvable`) and for solved boards the
+/// steps separated by `;`, all separated by tabs. Boards are listed from oldest to newest.
+#[derive(Clone, Debug)]
+pub struct SolutionCache {
+    path: PathBuf,
+    limit: usize,
+    /// Canonical boards with their outcome, oldest first.
+    entries: Vec<(Board, SolveResult)>,
+    /// Positions of the boards in `entries`.
+    positions: HashMap<Board, usize>,
+}
+
+impl SolutionCache {
+    /// Reads the cache from `path`, starting out empty if the file does not exist yet.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(error),
+        };
+
+        let mut cache = Self {
+            path,
+            limit: DEFAULT_LIMIT,
+            entries: Vec::new(),
+            positions: HashMap::new(),
+        };
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (board, result) = parse_line(line).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid cache entry on line {}", number + 1),
+                )
+            })?;
+            cache.insert_canonical(board, result);
+        }
+        Ok(cache)
+    }
+
+    /// Keeps at most `limit` boards, dropping the oldest ones when more are added.
+    ///
+    /// Without this, 10000 boards are kept.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self.prune(limit);
+        self
+    }
+
+    /// The file the cache is read from and saved to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The number of boards with a known outcome.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no outcome is known yet.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over all canonical boards with their outcome, oldest first.
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = (Board, &SolveResult)> {
+        self.entries.iter().map(|(board, result)| (*board, result))
+    }
+
+    /// Returns the known outcome for the board or any of its symmetric images, with the steps
+    /// mapped onto the board itself.
+    pub fn get(&self, board: Board) -> Option<SolveResult> {
+        let (canonical, symmetry) = board.canonical();
+        let (_, result) = &self.entries[*self.positions.get(&canonical)?];
+        Some(transform_result(result, symmetry.inverse()))
+    }
+
+    /// Remembers the outcome for the board, unless it is a timeout.
+    ///
+    /// Changes are only written to disk by [`SolutionCache::save`].
+    pub fn insert(&mut self, board: Board, result: &SolveResult) {
+        if *result == SolveResult::Timeout {
+            return;
+        }
+        let (canonical, symmetry) = board.canonical();
+        self.insert_canonical(canonical, transform_result(result, symmetry));
+        self.prune(self.limit);
+    }
+
+    /// Drops the oldest boards until at most `limit` are left.
+    pub fn prune(&mut self, limit: usize) {
+        if self.entries.len() > limit {
+            self.entries.drain(..self.entries.len() - limit);
+            self.reindex();
+        }
+    }
+
+    /// Forgets all boards.
+    ///
+    /// Like other changes, this is only written to disk by [`SolutionCache::save`].
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.positions.clear();
+    }
+
+    /// Writes all boards to the file, replacing its previous contents.
+    pub fn save(&self) -> io::Result<()> {
+        let mut text = String::new();
+        for (board, result) in &self.entries {
+            text += &format_line(*board, result);
+            text.push('\n');
+        }
+        fs::write(&self.path, text)
+    }
+
+    /// Adds or replaces a board that is already in canonical form, making it the newest one.
+    fn insert_canonical(&mut self, board: Board, result: SolveResult) {
+        if let Some(position) = self.positions.remove(&board) {
+            self.entries.remove(position);
+            self.reindex();
+        }
+        self.positions.insert(board, self.entries.len());
+        self.entries.push((board, result));
+    }
+
+    fn reindex(&mut self) {
+        self.positions = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(position, (board, _))| (*board, position))
+            .collect();
+    }
+}
+
+fn transform_result(result: &SolveResult, symmetry: Symmetry) -> SolveResult {
+    match result {
+        SolveResult::Solution(steps) => {
+            SolveResult::Solution(steps.iter().map(|step| step.transform(symmetry)).collect())
+        }
+        result => result.clone(),
+    }
+}
+
+fn format_line(board: Board, result: &SolveResult) -> String {
+    match result {
+        SolveResult::Solution(steps) => {
+            let steps = steps
+                .iter()
+                .map(Step::to_string)
+                .collect::<Vec<_>>()
+                .join(";");
+            format!("{board:#}\tsolved\t{steps}")
+        }
+        SolveResult::Unsolvable => format!("{board:#}\tunsolvable"),
+        SolveResult::Timeout => unreachable!("timeouts are never cached"),
+    }
+}
+
+fn parse_line(line: &str) -> Option<(Board, SolveResult)> {
+    let mut fields = line.split('\t');
+    let board = fields.next()?.parse().ok()?;
+    let result = match (fields.next()?, fields.next()) {
+        ("solved", steps) => SolveResult::Solution(
+            steps
+                .unwrap_or("")
+                .split(';')
+                .filter(|step| !step.is_empty())
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .ok()?,
+        ),
+        ("unsolvable", None) => SolveResult::Unsolvable,
+        _ => return None,
+    };
+    fields.next().is_none().then_some((board, result))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process, time::Duration};
+
+    use crate::generator::Generator;
+
+    use super::*;
+
+    #[test]
+    fn round_trip_with_symmetric_lookup() {
+        let path = env::temp_dir().join(format!("sigmars-cache-test-{}", process::id()));
+        let mut cache = SolutionCache::open(&path).unwrap();
+        assert!(cache.is_empty());
+
+        let mut generator = Generator::new(3).solvable(true);
+        let board = generator.generate();
+        let result = board.solve(Duration::from_secs(10));
+        cache.insert(board.board(), &result);
+        cache.insert(generator.generate().board(), &SolveResult::Timeout);
+        cache.save().unwrap();
+
+        let cache = SolutionCache::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(board.board()), Some(result));
+
+        // The image of the board gets the image of the solution, which clears it.
+        let image = board
+            .board()
+            .transform(Symmetry::reflection().then(Symmetry::rotation(2)));
+        let Some(SolveResult::Solution(steps)) = cache.get(image) else {
+            panic!("symmetric board should be found");
+        };
+        let mut image = image;
+        for step in steps {
+            image.remove(step);
+        }
+        assert!(image.is_solved());
+    }
+
+    #[test]
+    fn oldest_boards_are_dropped() {
+        let mut cache = SolutionCache::open("does-not-exist").unwrap().limit(2);
+        let mut generator = Generator::new(0);
+        let boards = (0..3)
+            .map(|_| generator.generate().board())
+            .collect::<Vec<_>>();
+        for &board in &boards {
+            cache.insert(board, &SolveResult::Unsolvable);
+        }
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(boards[0]), None);
+        assert_eq!(cache.get(boards[2]), Some(SolveResult::Unsolvable));
+
+        cache.prune(0);
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    error::Error,
+    fs,
+    io::{self, ErrorKind, Read},
+    num::NonZeroUsize,
+    path::Path,
+    process::ExitCode,
+    thread,
+    time::Duration,
+};
+
+use sigmars_garden_solver::{
+    Board, GamePosition, Generator, Hint, Layout, LayoutError, Outcome, Scanner, SolutionCache,
+    Solutions, SolveOptions, SolveResult, SolveStats, Step,
+};
+
+use crate::{play, CacheAction, Strategy};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Exit code when the board cannot be solved.
+pub(crate) const EXIT_UNSOLVABLE: u8 = 3;
+/// Exit code when the solver gave up.
+pub(crate) const EXIT_TIMEOUT: u8 = 4;
+
+/// Exit code when the steps do not clear the board.
+pub(crate) const EXIT_INVALID: u8 = 5;
+
+/// Reads a file, or stdin if there is no path or it is `-`.
+fn read_input(path: Option<&Path>) -> io::Result<Vec<u8>> {
+    match path {
+        Some(path) if path != Path::new("-") => fs::read(path),
+        _ => {
+            let mut bytes = Vec::new();
+            io::stdin().read_to_end(&mut bytes)?;
+            Ok(bytes)
+        }
+    }
+}
+
+/// Looks up a bundled layout by name, or reads it from a file otherwise.
+pub(crate) fn layout(name: &str) -> Result<Layout, Box<dyn Error>> {
+    if let Some(layout) = Layout::preset(name) {
+        return Ok(layout);
+    }
+    Layout::load(name).map_err(|error| match error {
+        LayoutError::Io(error) if error.kind() == ErrorKind::NotFound => {
+            let presets = Layout::PRESETS.map(|(preset, _)| preset).join(", ");
+            format!("no layout file `{name}`, and it is none of the presets {presets}").into()
+        }
+        error => error.into(),
+    })
+}
+
+/// Reads a board from a file or stdin, either in text notation or as a screenshot.
+pub(crate) fn read_board(path: Option<&Path>) -> Result<Board, Box<dyn Error>> {
+    let bytes = read_input(path)?;
+
+    if bytes.starts_with(PNG_SIGNATURE) {
+        let image = image::load_from_memory(&bytes)?.into_rgb8();
+        Ok(Scanner::new()
+            .scan_board(&image)
+            .ok_or("screenshot does not contain the board")?)
+    } else {
+        Ok(String::from_utf8(bytes)?.parse()?)
+    }
+}
+
+pub(crate) fn solve(
+    path: Option<&Path>,
+    options: SolveOptions,
+    print_stats: bool,
+    cache_path: Option<&Path>,
+    strategy: Strategy,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let board = read_board(path)?;
+    let position = GamePosition::new(board).ok_or("board is not a consistent game position")?;
+
+    let mut cache = cache_path.map(SolutionCache::open).transpose()?;
+    let result = match cache.as_ref().and_then(|cache| cache.get(board)) {
+        Some(result) => {
+            eprintln!("Found in cache.");
+            result
+        }
+        None => {
+            let (result, stats) = strategy.solver().solve(position, options);
+            if print_stats {
+                eprint_stats(&stats);
+            }
+            if let Some(cache) = &mut cache {
+                cache.insert(board, &result);
+                cache.save()?;
+            }
+            result
+        }
+    };
+
+    Ok(match result {
+        SolveResult::Solution(steps) => {
+            eprintln!("Solved in {} steps.", steps.len());
+            for step in steps {
+                println!("{step}");
+            }
+            ExitCode::SUCCESS
+        }
+        SolveResult::Unsolvable => {
+            eprintln!("Unsolvable!");
+            ExitCode::from(EXIT_UNSOLVABLE)
+        }
+        SolveResult::Timeout => {
+            eprintln!("Timeout!");
+            ExitCode::from(EXIT_TIMEOUT)
+        }
+    })
+}
+
+/// Prints hints for the board in the file, or on the screen at the layout if given.
+pub(crate) fn hint(
+    path: Option<&Path>,
+    screen: Option<&Layout>,
+    options: SolveOptions,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let position = if let Some(layout) = screen {
+        let image = play::capture_screen(layout.monitor)?;
+        layout.check(image.dimensions())?;
+        play::scanner(layout)
+            .scan_game_position(&image)
+            .ok_or("no game in progress found on the screen")?
+    } else {
+        GamePosition::new(read_board(path)?).ok_or("board is not a consistent game position")?
+    };
+
+    for Hint { step, outcome } in position.hints(options) {
+        let outcome = match outcome {
+            Outcome::Winning => "winning",
+            Outcome::Losing => "losing",
+            Outcome::Unknown => "unknown",
+        };
+        println!("{step}  {outcome}");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+pub(crate) fn rate(
+    path: Option<&Path>,
+    options: SolveOptions,
+    json: bool,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let board = read_board(path)?;
+    let position = GamePosition::new(board).ok_or("board is not a consistent game position")?;
+
+    let difficulty = position.difficulty(options);
+    let result = match difficulty.result {
+        SolveResult::Solution(_) => "solved",
+        SolveResult::Unsolvable => "unsolvable",
+        SolveResult::Timeout => "timeout",
+    };
+    if json {
+        println!(
+            concat!(
+                "{{",
+                r#""playout_success":{},"first_steps":{},"losing_first_steps":{},"#,
+                r#""unknown_first_steps":{},"result":"{}","nodes":{},"forced_steps":{}"#,
+                "}}"
+            ),
+            difficulty.playout_success,
+            difficulty.first_steps,
+            difficulty.losing_first_steps,
+            difficulty.unknown_first_steps,
+            result,
+            difficulty.nodes,
+            difficulty.forced_steps,
+        );
+    } else {
+        println!(
+            "Random playouts solved: {:.1}%",
+            difficulty.playout_success * 100.0
+        );
+        println!(
+            "Losing first steps:     {} of {} ({} unknown)",
+            difficulty.losing_first_steps, difficulty.first_steps, difficulty.unknown_first_steps
+        );
+        println!("Solver result:          {result}");
+        println!("Solver positions:       {}", difficulty.nodes);
+        println!("Forced steps:           {}", difficulty.forced_steps);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+pub(crate) fn solutions(
+    path: Option<&Path>,
+    limit: usize,
+    timeout: Duration,
+    list: bool,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let board = read_board(path)?;
+    let position = GamePosition::new(board).ok_or("board is not a consistent game position")?;
+
+    let count = |solutions: &Solutions| {
+        let plus = if solutions.complete { "" } else { "+" };
+        format!("{}{plus}", solutions.solutions.len())
+    };
+
+    for step in board.valid_steps() {
+        let mut next_board = board;
+        next_board.remove(step);
+        let next_position = GamePosition::new(next_board).expect("valid step keeps consistency");
+        println!(
+            "{step}  {}",
+            count(&next_position.solutions(limit, timeout))
+        );
+    }
+
+    let solutions = position.solutions(limit, timeout);
+    println!("Total: {}", count(&solutions));
+    if list {
+        for steps in &solutions.solutions {
+            println!();
+            for step in steps {
+                println!("{step}");
+            }
+        }
+    }
+
+    Ok(if solutions.solutions.is_empty() && solutions.complete {
+        ExitCode::from(EXIT_UNSOLVABLE)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+pub(crate) fn verify(
+    board_path: &Path,
+    steps_path: Option<&Path>,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let board = read_board(Some(board_path))?;
+    let steps = String::from_utf8(read_input(steps_path)?)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<Step>, _>>()?;
+
+    Ok(match sigmars_garden_solver::verify(board, &steps) {
+        Ok(()) => {
+            eprintln!("Valid solution in {} steps.", steps.len());
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("Invalid: {error}");
+            ExitCode::from(EXIT_INVALID)
+        }
+    })
+}
+
+pub(crate) fn cache(path: &Path, action: CacheAction) -> Result<ExitCode, Box<dyn Error>> {
+    let mut cache = SolutionCache::open(path)?;
+    match action {
+        CacheAction::Stats => {
+            let solved = cache
+                .entries()
+                .filter(|(_, result)| matches!(result, SolveResult::Solution(_)))
+                .count();
+            println!("Boards:     {}", cache.len());
+            println!("Solved:     {solved}");
+            println!("Unsolvable: {}", cache.len() - solved);
+        }
+        CacheAction::List => {
+            for (board, result) in cache.entries() {
+                match result {
+                    SolveResult::Solution(steps) => {
+                        println!("{board:#}  solved in {} steps", steps.len())
+                    }
+                    SolveResult::Unsolvable => println!("{board:#}  unsolvable"),
+                    SolveResult::Timeout => unreachable!("timeouts are never cached"),
+                }
+            }
+        }
+        CacheAction::Prune { keep } => {
+            let before = cache.len();
+            cache.prune(keep);
+            cache.save()?;
+            eprintln!("Dropped {} boards.", before - cache.len());
+        }
+        CacheAction::Clear => {
+            cache.clear();
+            cache.save()?;
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Resolves a `--threads` argument, where 0 stands for all available cores.
+pub(crate) fn thread_count(threads: usize) -> usize {
+    if threads == 0 {
+        thread::available_parallelism().map_or(1, NonZeroUsize::get)
+    } else {
+        threads
+    }
+}
+
+fn eprint_stats(stats: &SolveStats) {
+    eprintln!("Time:       {:.3}s", stats.elapsed.as_secs_f64());
+    eprintln!("Positions:  {}", stats.nodes);
+    eprintln!("Backtracks: {}", stats.backtracks);
+    eprintln!("Pruned:     {}", stats.pruned);
+    eprintln!("Max depth:  {}", stats.max_depth);
+    eprintln!("Branching factor per depth:");
+    for (depth, branching) in stats.branching.iter().enumerate() {
+        eprintln!(
+            "  {depth:>2}: {:>6.2} ({} positions)",
+            branching.factor(),
+            branching.nodes
+        );
+    }
+}
+
+pub(crate) fn generate(
+    seed: u64,
+    count: usize,
+    solvable: bool,
+    compact: bool,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let mut generator = Generator::new(seed).solvable(solvable);
+    for i in 0..count {
+        let board = generator.generate().board();
+        if compact {
+            println!("{board:#}");
+        } else {
+            if i != 0 {
+                println!();
+            }
+            println!("{board}");
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
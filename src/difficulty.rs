@@ -0,0 +1,188 @@
+//! Estimating how hard a position is for a human to clear.
+//!
+//! None of the measures is a difficulty on its own, but together they tell apart boards where
+//! almost anything works from ones that need a specific sequence of steps.
+
+use std::{sync::Arc, time::Duration};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{
+    bitboard::BitBoard,
+    board::Step,
+    hints::{self, Outcome},
+    search::{DeadPositions, DEAD_POSITIONS_MEMORY},
+    solver::{GamePosition, SolveOptions, SolveResult},
+};
+
+/// How many random games to play for [`Difficulty::playout_success`].
+const PLAYOUTS: usize = 1000;
+
+/// How long to search after each step when checking whether it loses.
+const STEP_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Measures of how hard a position is, as returned by [`GamePosition::difficulty`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difficulty {
+    /// The fraction of games that clear the board when picking one of the available steps at
+    /// random each time.
+    pub playout_success: f64,
+    /// The number of steps available right now.
+    pub first_steps: usize,
+    /// How many of the available steps make the board impossible to clear.
+    pub losing_first_steps: usize,
+    /// How many of the available steps could not be checked in time.
+    pub unknown_first_steps: usize,
+    /// The outcome of solving the position.
+    pub result: SolveResult,
+    /// The number of positions the solver visited.
+    pub nodes: u64,
+    /// The longest stretch of the solution in which every other available step loses, or 0 if no
+    /// solution was found.
+    ///
+    /// Steps that are the only one available are not a choice, so they neither count towards a
+    /// stretch nor end it.
+    pub forced_steps: usize,
+}
+
+pub(crate) fn rate(position: GamePosition, options: SolveOptions) -> Difficulty {
+    // All positions checked for hints come from the same board, so dead ends found after one step
+    // of the solution are still dead ends after the next.
+    let dead_positions = Arc::new(DeadPositions::with_memory(DEAD_POSITIONS_MEMORY));
+    let first_hints = hints::hints_sharing(position, step_options(options), &dead_positions);
+    let count = |outcome| {
+        first_hints
+            .iter()
+            .filter(|hint| hint.outcome == outcome)
+            .count()
+    };
+    let (result, stats) = position.solve_with(options);
+    let forced_steps = match &result {
+        SolveResult::Solution(steps) => forced_steps(position, steps, options, &dead_positions),
+        _ => 0,
+    };
+
+    Difficulty {
+        playout_success: playout_success(position),
+        first_steps: first_hints.len(),
+        losing_first_steps: count(Outcome::Losing),
+        unknown_first_steps: count(Outcome::Unknown),
+        result,
+        nodes: stats.nodes,
+        forced_steps,
+    }
+}
+
+fn step_options(options: SolveOptions) -> SolveOptions {
+    SolveOptions {
+        timeout: STEP_TIMEOUT,
+        ..options
+    }
+}
+
+/// Plays random games with a fixed seed, so that the same position always gets the same rating.
+fn playout_success(position: GamePosition) -> f64 {
+    let mut rng = StdRng::seed_from_u64(0);
+    let initial_board = BitBoard::from(position.board());
+    let rules = position.rules();
+    let successes = (0..PLAYOUTS)
+        .filter(|_| {
+            let mut board = initial_board;
+            while let Some(step) = board.valid_steps(&rules).choose(&mut rng) {
+                board.remove(step.0[0]);
+                board.remove(step.0[1]);
+            }
+            board.is_solved()
+        })
+        .count();
+    successes as f64 / PLAYOUTS as f64
+}
+
+/// Walks along the solution, counting the steps that are the only one of several not known to
+/// lose.
+fn forced_steps(
+    position: GamePosition,
+    steps: &[Step],
+    options: SolveOptions,
+    dead_positions: &Arc<DeadPositions>,
+) -> usize {
+    let mut board = position.board();
+    let mut longest = 0;
+    let mut current = 0;
+    for &step in steps {
+        let position = GamePosition::with_rules(board, position.rules())
+            .expect("solution keeps the board consistent");
+        let hints = hints::hints_sharing(position, step_options(options), dead_positions);
+        let open = hints
+            .iter()
+            .filter(|hint| hint.outcome != Outcome::Losing)
+            .count();
+        if hints.len() > 1 {
+            if open == 1 {
+                current += 1;
+                longest = longest.max(current);
+            } else {
+                current = 0;
+            }
+        }
+        board.remove(step);
+    }
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::Board, element::Element};
+
+    use super::*;
+
+    #[test]
+    fn single_pair_is_trivial() {
+        let board = Board::with_elements(&[(0, 0, Element::Fire), (0, 1, Element::Fire)]);
+        let difficulty = GamePosition::new(board)
+            .unwrap()
+            .difficulty(SolveOptions::default());
+        assert_eq!(difficulty.playout_success, 1.0);
+        assert_eq!(difficulty.first_steps, 1);
+        assert_eq!(difficulty.losing_first_steps, 0);
+        // The only step available is not a choice.
+        assert_eq!(difficulty.forced_steps, 0);
+    }
+
+    #[test]
+    fn salt_pairing_with_itself_is_a_trap() {
+        let board = Board::with_elements(&[
+            (0, 0, Element::Salt),
+            (5, 4, Element::Salt),
+            (5, 5, Element::Fire),
+            (-5, -5, Element::Water),
+        ]);
+        let difficulty = GamePosition::new(board)
+            .unwrap()
+            .difficulty(SolveOptions::default());
+        // One of the five steps loses, which leaves four to choose from, and after any of them
+        // the last pair is the only step available.
+        assert_eq!(difficulty.first_steps, 5);
+        assert_eq!(difficulty.losing_first_steps, 1);
+        assert!(difficulty.playout_success > 0.7 && difficulty.playout_success < 0.9);
+        assert_eq!(difficulty.forced_steps, 0);
+    }
+
+    #[test]
+    fn only_step_that_does_not_lose_is_forced() {
+        // The water in the center is blocked, so the salt either goes with the fire or with the
+        // other water, which leaves the fire without a partner.
+        let board = Board::with_elements(&[
+            (-1, 0, Element::Fire),
+            (0, 0, Element::Water),
+            (1, 0, Element::Salt),
+            (1, 1, Element::Water),
+        ]);
+        let difficulty = GamePosition::new(board)
+            .unwrap()
+            .difficulty(SolveOptions::default());
+        assert_eq!(difficulty.first_steps, 2);
+        assert_eq!(difficulty.losing_first_steps, 1);
+        assert_eq!(difficulty.forced_steps, 1);
+    }
+}
@@ -1,30 +1,83 @@
+/// The different kinds of marbles on the board.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) enum Element {
+pub enum Element {
+    /// Pairs with itself or any of the cardinal elements.
     Salt = 1,
+    /// A cardinal element, pairing with itself or salt.
     Air,
+    /// A cardinal element, pairing with itself or salt.
     Fire,
+    /// A cardinal element, pairing with itself or salt.
     Water,
+    /// A cardinal element, pairing with itself or salt.
     Earth,
+    /// Pairs with mors.
     Vitae,
+    /// Pairs with vitae.
     Mors,
+    /// Pairs with any metal but gold.
     Quicksilver,
+    /// The first metal to be removed, with quicksilver.
     Lead,
+    /// The second metal to be removed, with quicksilver.
     Tin,
+    /// The third metal to be removed, with quicksilver.
     Iron,
+    /// The fourth metal to be removed, with quicksilver.
     Copper,
+    /// The fifth metal to be removed, with quicksilver.
     Silver,
+    /// The last metal, removed on its own once all others are gone.
     Gold,
 }
 
 impl Element {
-    pub(crate) fn to_index(element: Option<Element>) -> u8 {
+    /// All elements, in order.
+    pub const ALL: [Element; 14] = [
+        Self::Salt,
+        Self::Air,
+        Self::Fire,
+        Self::Water,
+        Self::Earth,
+        Self::Vitae,
+        Self::Mors,
+        Self::Quicksilver,
+        Self::Lead,
+        Self::Tin,
+        Self::Iron,
+        Self::Copper,
+        Self::Silver,
+        Self::Gold,
+    ];
+
+    /// The four elements that can be paired with themselves or with salt.
+    pub const CARDINALS: [Element; 4] = [Self::Air, Self::Fire, Self::Water, Self::Earth];
+
+    /// The metals that have to be paired with quicksilver, in the order they have to be removed.
+    ///
+    /// Gold is not included, since it is removed on its own after all other metals.
+    pub const METALS: [Element; 5] = [
+        Self::Lead,
+        Self::Tin,
+        Self::Iron,
+        Self::Copper,
+        Self::Silver,
+    ];
+
+    /// Converts to a number in `0..=14`, with `0` being no element.
+    pub fn to_index(element: Option<Element>) -> u8 {
         match element {
             Some(element) => element as u8,
             None => 0,
         }
     }
 
-    pub(crate) fn from_index(index: u8) -> Option<Self> {
+    /// Inverse of [`Element::to_index`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is larger than `14`.
+    pub fn from_index(index: u8) -> Option<Self> {
         match index {
             0 => None,
             1 => Some(Self::Salt),
@@ -44,4 +97,45 @@ impl Element {
             _ => panic!("invalid element index"),
         }
     }
+
+    /// The character used for the element in the [text notation](crate::notation).
+    pub fn symbol(self) -> char {
+        match self {
+            Self::Salt => 's',
+            Self::Air => 'a',
+            Self::Fire => 'f',
+            Self::Water => 'w',
+            Self::Earth => 'e',
+            Self::Vitae => 'v',
+            Self::Mors => 'm',
+            Self::Quicksilver => 'q',
+            Self::Lead => 'L',
+            Self::Tin => 'T',
+            Self::Iron => 'I',
+            Self::Copper => 'C',
+            Self::Silver => 'S',
+            Self::Gold => 'G',
+        }
+    }
+
+    /// Inverse of [`Element::symbol`].
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        Some(match symbol {
+            's' => Self::Salt,
+            'a' => Self::Air,
+            'f' => Self::Fire,
+            'w' => Self::Water,
+            'e' => Self::Earth,
+            'v' => Self::Vitae,
+            'm' => Self::Mors,
+            'q' => Self::Quicksilver,
+            'L' => Self::Lead,
+            'T' => Self::Tin,
+            'I' => Self::Iron,
+            'C' => Self::Copper,
+            'S' => Self::Silver,
+            'G' => Self::Gold,
+            _ => return None,
+        })
+    }
 }
@@ -0,0 +1,238 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    bitboard::{adjacent_mask, bits, free_mask},
+    board::Board,
+    element::Element,
+    index::{SigCoord, SigIndex},
+    rules::RuleSet,
+    solver::InitialBoard,
+    symmetry::Symmetry,
+};
+
+/// Generated boards follow the standard rules.
+const FREE_NEIGHBORS: u8 = RuleSet::STANDARD.free_neighbors;
+
+/// How often to try placing a pair of elements before starting over with a new layout.
+const PLACEMENT_ATTEMPTS: usize = 64;
+
+/// Creates random boards similar to the ones dealt by the game.
+///
+/// Just like in the game, gold is placed in the center and the remaining elements are placed on a
+/// layout that looks the same when rotated by 60 degrees.
+///
+/// The same seed always produces the same sequence of boards.
+#[derive(Clone, Debug)]
+pub struct Generator {
+    rng: StdRng,
+    solvable: bool,
+}
+
+impl Generator {
+    /// Creates a generator that deals the same boards for the same seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            solvable: false,
+        }
+    }
+
+    /// Only generate boards that are known to be solvable.
+    ///
+    /// Such boards are created backwards, starting with an empty board and putting back pairs of
+    /// elements so that they would be free to be removed again.
+    pub fn solvable(mut self, solvable: bool) -> Self {
+        self.solvable = solvable;
+        self
+    }
+
+    /// Deals the next board.
+    pub fn generate(&mut self) -> InitialBoard {
+        let board = if self.solvable {
+            loop {
+                let layout = self.layout();
+                if let Some(board) = self.place_backwards(&layout) {
+                    break board;
+                }
+            }
+        } else {
+            let layout = self.layout();
+            self.place_randomly(&layout)
+        };
+        InitialBoard::new(board).expect("generated board should be valid")
+    }
+
+    /// Picks 9 of the 15 groups of positions that are rotations of each other, excluding the
+    /// center, which gives space for the 54 elements besides gold.
+    fn layout(&mut self) -> Vec<SigIndex> {
+        let mut orbits = Vec::<[SigIndex; 6]>::new();
+        for index in SigIndex::all() {
+            let coord = SigCoord::from(index);
+            if coord.row() == 0 && coord.col() == 0 {
+                continue;
+            }
+            if orbits.iter().flatten().all(|&other| other != index) {
+                let mut orbit = [index; 6];
+                for i in 1..6 {
+                    orbit[i] = orbit[i - 1].transform(Symmetry::rotation(1));
+                }
+                orbits.push(orbit);
+            }
+        }
+
+        orbits
+            .choose_multiple(&mut self.rng, 9)
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    fn place_randomly(&mut self, layout: &[SigIndex]) -> Board {
+        let mut elements = pairs_to_remove(&mut self.rng)
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        elements.shuffle(&mut self.rng);
+
+        let mut board = center_gold();
+        for (&index, element) in layout.iter().zip(elements) {
+            board.set(index, Some(element));
+        }
+        board
+    }
+
+    fn place_backwards(&mut self, layout: &[SigIndex]) -> Option<Board> {
+        let mut board = center_gold();
+        let mut occupied = 1 << center().to_bit();
+        let mut remaining = layout
+            .iter()
+            .fold(0, |mask, index| mask | 1 << index.to_bit());
+
+        for [element1, element2] in pairs_to_remove(&mut self.rng).into_iter().rev() {
+            let candidates = bits(remaining)
+                .filter(|index| {
+                    free_mask(occupied | 1 << index.to_bit(), FREE_NEIGHBORS) & 1 << index.to_bit()
+                        != 0
+                })
+                .collect::<Vec<_>>();
+
+            // Positions surrounded by many others are hard to free up, so they are filled first,
+            // preferably next to already placed elements, growing the board outwards.
+            let weight = |index: &SigIndex| {
+                let adjacent = adjacent_mask(1 << index.to_bit());
+                1 << (3 * (adjacent & (occupied | remaining)).count_ones()
+                    + (adjacent & occupied).count_ones())
+            };
+
+            let (index1, index2) = (0..PLACEMENT_ATTEMPTS).find_map(|_| {
+                let index1 = *candidates.choose_weighted(&mut self.rng, weight).ok()?;
+                let index2 = *candidates
+                    .choose_weighted(&mut self.rng, |index| {
+                        if *index == index1 {
+                            0
+                        } else {
+                            weight(index)
+                        }
+                    })
+                    .ok()?;
+                let pair_mask = 1 << index1.to_bit() | 1 << index2.to_bit();
+                (free_mask(occupied | pair_mask, FREE_NEIGHBORS) & pair_mask == pair_mask)
+                    .then_some((index1, index2))
+            })?;
+
+            board.set(index1, Some(element1));
+            board.set(index2, Some(element2));
+            let pair_mask = 1 << index1.to_bit() | 1 << index2.to_bit();
+            occupied |= pair_mask;
+            remaining &= !pair_mask;
+        }
+
+        Some(board)
+    }
+}
+
+/// A random order in which the elements besides gold could be removed as pairs.
+///
+/// The metals are always in the right order, with all other pairs randomly spread around them.
+fn pairs_to_remove(rng: &mut impl Rng) -> Vec<[Element; 2]> {
+    let mut pairs = vec![[Element::Vitae, Element::Mors]; 4];
+
+    // Salt pairs up with itself, or two salts with two of the same cardinal element.
+    let mut cardinal_counts = [8; 4];
+    for _ in 0..2 {
+        if rng.gen() {
+            pairs.push([Element::Salt; 2]);
+        } else {
+            let cardinal = rng.gen_range(0..4);
+            cardinal_counts[cardinal] -= 2;
+            pairs.extend([[Element::Salt, Element::CARDINALS[cardinal]]; 2]);
+        }
+    }
+
+    for (element, count) in Element::CARDINALS.into_iter().zip(cardinal_counts) {
+        pairs.extend(vec![[element; 2]; count / 2]);
+    }
+
+    pairs.shuffle(rng);
+
+    let mut metal_positions = (0..pairs.len() + Element::METALS.len())
+        .collect::<Vec<_>>()
+        .choose_multiple(rng, Element::METALS.len())
+        .copied()
+        .collect::<Vec<_>>();
+    metal_positions.sort_unstable();
+    for (position, metal) in metal_positions.into_iter().zip(Element::METALS) {
+        pairs.insert(position, [metal, Element::Quicksilver]);
+    }
+
+    pairs
+}
+
+fn center() -> SigIndex {
+    SigIndex::from(SigCoord::new(0, 0).unwrap())
+}
+
+fn center_gold() -> Board {
+    let mut board = Board::new();
+    board.set(center(), Some(Element::Gold));
+    board
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::solver::SolveResult;
+
+    use super::*;
+
+    #[test]
+    fn layout_is_symmetric() {
+        let mut generator = Generator::new(0);
+        for _ in 0..10 {
+            let board = generator.generate().board();
+            for index in SigIndex::all() {
+                let rotated = index.transform(Symmetry::rotation(1));
+                assert_eq!(board.get(index).is_some(), board.get(rotated).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_boards() {
+        let mut generator1 = Generator::new(42).solvable(true);
+        let mut generator2 = Generator::new(42).solvable(true);
+        for _ in 0..10 {
+            assert_eq!(generator1.generate(), generator2.generate());
+        }
+    }
+
+    #[test]
+    fn solvable_boards_are_solvable() {
+        let mut generator = Generator::new(0).solvable(true);
+        for _ in 0..10 {
+            let result = generator.generate().solve(Duration::from_secs(60));
+            assert!(matches!(result, SolveResult::Solution(_)));
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Telling which of the steps available in a position still lead to a solution.
+//!
+//! Each step is checked with its own search, but all of them share the table of dead positions,
+//! since a position that cannot be cleared after one step cannot be cleared after another either.
+
+use std::sync::Arc;
+
+use crate::{
+    bitboard::BitBoard,
+    board::Step,
+    search::{Budget, DeadPositions, Search, SearchControl, SearchStatus, DEAD_POSITIONS_MEMORY},
+    solver::{GamePosition, SolveOptions},
+};
+
+/// Whether a step keeps the position solvable, as found by [`GamePosition::hints`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The board can still be cleared after the step.
+    Winning,
+    /// The board can no longer be cleared after the step.
+    Losing,
+    /// The search ran out of time before finding out.
+    Unknown,
+}
+
+/// A step that can be made right now, along with its [`Outcome`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hint {
+    /// The step that can be made.
+    pub step: Step,
+    /// Whether the board can still be cleared after the step.
+    pub outcome: Outcome,
+}
+
+pub(crate) fn hints(position: GamePosition, options: SolveOptions) -> Vec<Hint> {
+    let dead_positions = Arc::new(DeadPositions::with_memory(DEAD_POSITIONS_MEMORY));
+    hints_sharing(position, options, &dead_positions)
+}
+
+/// Same as [`hints`], but remembering dead positions in the given table, e.g. to share it between
+/// positions of the same game.
+pub(crate) fn hints_sharing(
+    position: GamePosition,
+    options: SolveOptions,
+    dead_positions: &Arc<DeadPositions>,
+) -> Vec<Hint> {
+    let initial_board = BitBoard::from(position.board());
+    let rules = position.rules();
+    rules
+        .valid_steps(position.board())
+        .into_iter()
+        .map(|step| {
+            let mut search = Search::below(
+                initial_board,
+                rules,
+                vec![step],
+                dead_positions.clone(),
+                SearchControl::default(),
+            )
+            .pruning(options.pruning);
+            let outcome = match search.run(Budget::Time(options.timeout)) {
+                SearchStatus::Solved(_) => Outcome::Winning,
+                SearchStatus::Unsolvable => Outcome::Losing,
+                SearchStatus::Paused | SearchStatus::Cancelled => Outcome::Unknown,
+            };
+            Hint { step, outcome }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{board::Board, element::Element};
+
+    use super::*;
+
+    #[test]
+    fn pairing_salt_with_itself_loses() {
+        // The fire and the water both need one of the salts.
+        let board = Board::with_elements(&[
+            (0, 0, Element::Salt),
+            (5, 4, Element::Salt),
+            (5, 5, Element::Fire),
+            (-5, -5, Element::Water),
+        ]);
+        let position = GamePosition::new(board).unwrap();
+
+        let options = SolveOptions {
+            timeout: Duration::from_secs(1),
+            ..SolveOptions::default()
+        };
+        let hints = position.hints(options);
+        assert_eq!(hints.len(), position.board().valid_steps().len());
+        for hint in hints {
+            let Step([a, b]) = hint.step;
+            let expected = if [board.get(a), board.get(b)] == [Some(Element::Salt); 2] {
+                Outcome::Losing
+            } else {
+                Outcome::Winning
+            };
+            assert_eq!(hint.outcome, expected, "{}", hint.step);
+        }
+    }
+}
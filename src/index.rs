@@ -1,17 +1,40 @@
 /// A position on the board as an index.
 ///
 /// Starts at 0 in the top right corner, moving right and then down, ending at 90.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) struct SigIndex(i8);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SigIndex(i8);
 
 impl SigIndex {
-    pub(crate) fn all() -> impl ExactSizeIterator<Item = SigIndex> + Clone {
+    /// Returns the index if it is on the playing field, i.e. in the range `0..91`.
+    pub fn new(index: i8) -> Option<Self> {
+        (0..91).contains(&index).then_some(Self(index))
+    }
+
+    /// Iterates over all positions on the playing field in order.
+    pub fn all() -> impl ExactSizeIterator<Item = SigIndex> + Clone {
         (0..91).map(SigIndex)
     }
 
-    pub(crate) fn to_usize(self) -> usize {
+    /// The index as a [`usize`] in the range `0..91`.
+    pub fn to_usize(self) -> usize {
         self.0 as usize
     }
+
+    /// The position of this index in a [`BitBoard`](crate::bitboard::BitBoard).
+    pub(crate) fn to_bit(self) -> u32 {
+        BIT_TABLES.0[self.to_usize()] as u32
+    }
+
+    /// Inverse of [`SigIndex::to_bit`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit is outside of the playing field.
+    pub(crate) fn from_bit(bit: u32) -> Self {
+        let index = BIT_TABLES.1[bit as usize];
+        assert!(index >= 0, "bit outside of the playing field");
+        Self(index)
+    }
 }
 
 impl From<SigCoord> for SigIndex {
@@ -26,28 +49,31 @@ impl From<SigCoord> for SigIndex {
 /// - Rows go from -5 (top) to 5 (bottom).
 /// - Columns are skewed, so that the top center (0, -5) is the top right corner.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) struct SigCoord {
+pub struct SigCoord {
     row: i8,
     col: i8,
 }
 
 impl SigCoord {
-    fn new(row: i8, col: i8) -> Option<Self> {
+    /// Returns the coordinate if it is on the playing field.
+    pub fn new(row: i8, col: i8) -> Option<Self> {
         (row.abs() <= 5 && col.abs() <= 5 && (row - col).abs() <= 5).then_some(Self { row, col })
     }
 
-    pub(crate) fn row(self) -> i8 {
+    /// The row, from -5 at the top to 5 at the bottom.
+    pub fn row(self) -> i8 {
         self.row
     }
 
-    pub(crate) fn col(self) -> i8 {
+    /// The skewed column, from -5 to 5.
+    pub fn col(self) -> i8 {
         self.col
     }
 
     /// Iterates over all adjacent coordinates in clockwise order, starting at the right.
     ///
     /// Positions that are outside the playing field yield [`None`].
-    pub(crate) fn adjacent_cw(self) -> [Option<SigCoord>; 6] {
+    pub fn adjacent_cw(self) -> [Option<SigCoord>; 6] {
         let Self { row, col } = self;
         [
             Self::new(row + 1, col),
@@ -78,10 +104,36 @@ impl From<SigIndex> for SigCoord {
     }
 }
 
-fn row_index_offset(row: i8) -> i8 {
+const fn row_index_offset(row: i8) -> i8 {
     45 + row * 11 - row * (row.abs() + 1) / 2
 }
 
+/// Lookup tables to convert between [`SigIndex`] and bits of a [`BitBoard`].
+///
+/// Bits are laid out as an 11x11 grid of [`SigCoord`]s, with `-1` marking bits outside of the
+/// playing field.
+///
+/// [`BitBoard`]: crate::bitboard::BitBoard
+const BIT_TABLES: ([u8; 91], [i8; 121]) = {
+    let mut index_to_bit = [0; 91];
+    let mut bit_to_index = [-1; 121];
+    let mut row: i8 = -5;
+    while row <= 5 {
+        let mut col: i8 = -5;
+        while col <= 5 {
+            if (row - col).abs() <= 5 {
+                let index = col + row_index_offset(row);
+                let bit = (row + 5) as usize * 11 + (col + 5) as usize;
+                index_to_bit[index as usize] = bit as u8;
+                bit_to_index[bit] = index;
+            }
+            col += 1;
+        }
+        row += 1;
+    }
+    (index_to_bit, bit_to_index)
+};
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeSet;
@@ -97,4 +149,14 @@ mod tests {
             assert_eq!(SigIndex::from(coord), SigIndex(index));
         }
     }
+
+    #[test]
+    fn bit_index_conversion() {
+        for index in SigIndex::all() {
+            let coord = SigCoord::from(index);
+            let bit = index.to_bit();
+            assert_eq!(bit, ((coord.row + 5) * 11 + coord.col + 5) as u32);
+            assert_eq!(SigIndex::from_bit(bit), index);
+        }
+    }
 }
@@ -0,0 +1,70 @@
+//! A solver for the Sigmar's Garden minigame in [Opus Magnum](https://www.zachtronics.com/opus-magnum/).
+//!
+//! - A [`Board`] holds the [`Element`]s on each position of the playing field, addressed by either
+//!   a [`SigIndex`] or a [`SigCoord`]. [Applying](Board::apply) a [`Step`] checks that it follows
+//!   the rules of the game, and [`verify()`] checks that a whole solution clears a board.
+//! - An [`InitialBoard`] is a freshly dealt game, which can be [solved](InitialBoard::solve) into a
+//!   list of [`Step`]s.
+//! - A [`GamePosition`] is any board that can occur while playing, e.g. after a few steps were
+//!   already made, and can be solved the same way, or have all its [`Solutions`] enumerated.
+//!   [`Hint`]s tell which of the steps available right now still lead to a solution, and its
+//!   [`Difficulty`] estimates how hard it is to clear.
+//! - A [`Search`] does the same, but can be run in portions and controlled from other threads.
+//!   Other strategies like a [`BestFirst`] search or randomized [`Restarts`] are available through
+//!   the [`Solver`] trait.
+//! - A [`RuleSet`] decides which elements pair up and when they are free. Boards follow the
+//!   [standard rules](RuleSet::STANDARD) unless positions are created
+//!   [with other rules](GamePosition::with_rules).
+//! - A [`SolutionCache`] remembers outcomes across runs, including for symmetric boards.
+//! - A [`Scanner`] reads a board from a screenshot of the game, at the [`Geometry`] it locates or
+//!   the one of a [`Layout`] loaded from a file.
+//! - A [`Symmetry`] maps boards and steps to their rotated or mirrored images.
+//! - Boards can be written down and parsed using a [text notation](notation).
+//! - A [`Generator`] deals random boards, e.g. for testing.
+
+#![warn(missing_docs)]
+
+mod best_first;
+mod bitboard;
+mod board;
+mod cache;
+mod difficulty;
+mod element;
+mod generator;
+mod hints;
+mod index;
+pub mod notation;
+mod parallel;
+mod pruning;
+mod restarts;
+mod rules;
+mod scanner;
+mod screen;
+mod search;
+mod solutions;
+mod solver;
+mod symmetry;
+mod verify;
+
+pub use best_first::BestFirst;
+pub use board::{Board, Step, StepError};
+pub use cache::SolutionCache;
+pub use difficulty::Difficulty;
+pub use element::Element;
+pub use generator::Generator;
+pub use hints::{Hint, Outcome};
+pub use index::{SigCoord, SigIndex};
+pub use notation::{ParseBoardError, ParseStepError};
+pub use pruning::Pruning;
+pub use restarts::Restarts;
+pub use rules::RuleSet;
+pub use scanner::Scanner;
+pub use screen::{Geometry, Layout, LayoutError};
+pub use search::{Budget, Search, SearchControl, SearchHandle, SearchStatus};
+pub use solutions::Solutions;
+pub use solver::{
+    Branching, DepthFirst, GamePosition, InitialBoard, SolveOptions, SolveResult, SolveStats,
+    Solver,
+};
+pub use symmetry::Symmetry;
+pub use verify::{verify, VerifyError};
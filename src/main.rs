@@ -1,140 +1,337 @@
-pub(crate) mod board;
-pub(crate) mod element;
-pub(crate) mod index;
-pub(crate) mod mouse;
-pub(crate) mod scanner;
-pub(crate) mod screen;
-pub(crate) mod solver;
+mod bench;
+mod commands;
+mod mouse;
+mod play;
 
-use std::{
-    io::{stdout, Write},
-    thread::sleep,
-    time::Duration,
-};
+use std::{path::PathBuf, process::ExitCode, time::Duration};
 
-use board::Step;
-use image::{DynamicImage, RgbImage};
-use mouse_rs::Mouse;
-use scanner::Scanner;
-use screenshots::Screen;
-use solver::InitialBoard;
+use clap::{Parser, Subcommand, ValueEnum};
+use sigmars_garden_solver::{BestFirst, DepthFirst, Pruning, Restarts, SolveOptions, Solver};
 
-use crate::solver::SolveResult;
+/// Where known outcomes are remembered unless another file is given.
+const DEFAULT_CACHE_FILE: &str = "sigmars-cache.txt";
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-enum BoardState {
-    SleepSearch,
-    SleepValidate(InitialBoard),
-    SleepUnsolvable(InitialBoard),
-    Search,
-    Validate(InitialBoard),
-    Ready(InitialBoard),
-    Solve(Vec<Step>),
-    Unsolvable(InitialBoard),
-}
+/// The screen layout used unless another one is given.
+const DEFAULT_LAYOUT: &str = "auto";
 
-fn main() {
-    let mouse = Mouse::new();
-    let scanner = Scanner::new();
+#[derive(Parser)]
+#[command(about, version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-    let mut state = BoardState::Search;
-    loop {
-        match state {
-            BoardState::SleepSearch => {
-                sleep(Duration::from_secs(1));
-                state = BoardState::Search;
-            }
-            BoardState::SleepValidate(board) => {
-                print!("Making sure nothing is moving...");
-                stdout().flush().unwrap();
-                sleep(Duration::from_secs(2));
-                state = BoardState::Validate(board);
-            }
-            BoardState::SleepUnsolvable(board) => {
-                sleep(Duration::from_secs(1));
-                state = BoardState::Unsolvable(board);
-            }
-            BoardState::Search => {
-                let image = capture_first_screen();
+#[derive(Subcommand)]
+enum Command {
+    /// Plays the game by scanning the screen and clicking the solution (default).
+    Play {
+        /// File to remember outcomes in, so boards seen before are not solved again.
+        #[arg(long, default_value = DEFAULT_CACHE_FILE)]
+        cache: PathBuf,
+        /// Neither read nor write the cache.
+        #[arg(long)]
+        no_cache: bool,
+        /// Where the game is on the screen: one of the presets `auto` and `fixed`, or a TOML
+        /// file.
+        #[arg(long, default_value = DEFAULT_LAYOUT)]
+        layout: String,
+    },
+    /// Solves a single board and prints the steps, one per line as `row,col row,col`.
+    ///
+    /// Exits with 0 if solved, 3 if unsolvable and 4 on timeout.
+    Solve {
+        /// File with the board in text notation or a screenshot; reads stdin if omitted or `-`.
+        file: Option<PathBuf>,
+        /// Seconds to search before giving up.
+        #[arg(long, default_value = "5", value_parser = parse_timeout)]
+        timeout: Duration,
+        /// Print statistics about the search to stderr.
+        #[arg(long)]
+        stats: bool,
+        /// Number of threads to search with, or 0 to use all cores.
+        #[arg(long, default_value_t = 1)]
+        threads: usize,
+        /// File to look up the board in before solving it and to remember the outcome in.
+        #[arg(long)]
+        cache: Option<PathBuf>,
+        /// How to search for a solution.
+        #[arg(long, value_enum, default_value_t = Strategy::DepthFirst)]
+        strategy: Strategy,
+    },
+    /// Tells for each step available right now whether the board can still be cleared afterwards.
+    ///
+    /// Each step is printed as `row,col row,col` followed by `winning`, `losing` or `unknown` if
+    /// the search ran out of time.
+    Hint {
+        /// File with the board in text notation or a screenshot; reads stdin if omitted or `-`.
+        file: Option<PathBuf>,
+        /// Take a screenshot of the game instead of reading a file.
+        #[arg(long, conflicts_with = "file")]
+        screen: bool,
+        /// Where the game is on the screen with `--screen`, like for `play`.
+        #[arg(long, default_value = DEFAULT_LAYOUT, requires = "screen")]
+        layout: String,
+        /// Seconds to search after each step before giving up on it.
+        #[arg(long, default_value = "1", value_parser = parse_timeout)]
+        timeout: Duration,
+    },
+    /// Estimates how hard a board is to clear and prints the measures it is based on.
+    Rate {
+        /// File with the board in text notation or a screenshot; reads stdin if omitted or `-`.
+        file: Option<PathBuf>,
+        /// Seconds to search for a solution before giving up.
+        #[arg(long, default_value = "5", value_parser = parse_timeout)]
+        timeout: Duration,
+        /// Number of threads to search with, or 0 to use all cores.
+        #[arg(long, default_value_t = 1)]
+        threads: usize,
+        /// Print the rating as a single line of JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Counts the distinct solutions of a board, overall and for each possible first step.
+    ///
+    /// Solutions that only differ in the order of the same pairs are counted once. Exits with 3 if
+    /// the board is unsolvable.
+    Solutions {
+        /// File with the board in text notation or a screenshot; reads stdin if omitted or `-`.
+        file: Option<PathBuf>,
+        /// Stop counting after this many solutions, shown as `N+`.
+        #[arg(long, default_value_t = 1000)]
+        limit: usize,
+        /// Seconds to count before giving up, also shown as `N+`, for each first step and the
+        /// total.
+        #[arg(long, default_value = "5", value_parser = parse_timeout)]
+        timeout: Duration,
+        /// Print every solution found, separated by empty lines.
+        #[arg(long)]
+        list: bool,
+    },
+    /// Replays steps on a board, checking each against the rules, and tells whether it is cleared.
+    ///
+    /// The steps are read one per line as printed by `solve`. Exits with 0 if they clear the board
+    /// and 5 if a step is illegal or elements are left over.
+    Verify {
+        /// File with the board in text notation or a screenshot.
+        board: PathBuf,
+        /// File with the steps; reads stdin if omitted or `-`.
+        steps: Option<PathBuf>,
+    },
+    /// Inspects or prunes the file of remembered outcomes.
+    Cache {
+        /// The cache file.
+        #[arg(long, default_value = DEFAULT_CACHE_FILE)]
+        file: PathBuf,
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Generates random boards in text notation.
+    Generate {
+        /// Seed for the random number generator.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Number of boards to generate.
+        #[arg(long, default_value_t = 1)]
+        count: usize,
+        /// Only generate boards that are known to be solvable.
+        #[arg(long)]
+        solvable: bool,
+        /// Print each board on a single line.
+        #[arg(long)]
+        compact: bool,
+    },
+    /// Solves many generated boards and reports statistics about the solver.
+    Bench {
+        /// Seed for the random number generator.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Number of boards to solve.
+        #[arg(long, default_value_t = 1000)]
+        count: usize,
+        /// Only use boards that are known to be solvable.
+        #[arg(long)]
+        solvable: bool,
+        /// Seconds to search before giving up on a board.
+        #[arg(long, default_value = "5", value_parser = parse_timeout)]
+        timeout: Duration,
+        /// Number of threads to search with, or 0 to use all cores.
+        #[arg(long, default_value_t = 1)]
+        threads: usize,
+        /// How to search for solutions; given several times, each one solves the same boards.
+        #[arg(long, value_enum, default_values_t = [Strategy::DepthFirst])]
+        strategy: Vec<Strategy>,
+        /// Turn off the check that salt can pair up with all cardinal elements.
+        #[arg(long)]
+        no_salt_pruning: bool,
+        /// Turn off the check that there are as many vitae as mors.
+        #[arg(long)]
+        no_vitae_mors_pruning: bool,
+        /// Turn off the checks that each metal has a quicksilver and can still be freed.
+        #[arg(long)]
+        no_metal_pruning: bool,
+        /// Print the report as a single line of JSON.
+        #[arg(long)]
+        json: bool,
+    },
+}
 
-                state = match scanner.scan_image(&image) {
-                    Some(board) => {
-                        println!("Found a valid board!");
-                        BoardState::SleepValidate(board)
-                    }
-                    None => BoardState::SleepSearch,
-                };
-            }
-            BoardState::Validate(board) => {
-                let image = capture_first_screen();
+/// A way to search for a solution, see [`Solver`].
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Strategy {
+    /// Tries steps in a fixed order and backtracks, which is the default.
+    DepthFirst,
+    /// Expands the most promising position first.
+    BestFirst,
+    /// Restarts the depth-first search with a different order of equally promising steps.
+    Restarts,
+}
 
-                state = match scanner.scan_image(&image) {
-                    Some(confirmation_board) if board == confirmation_board => {
-                        println!(" Ready!");
-                        BoardState::Ready(board)
-                    }
-                    _ => {
-                        println!(" Board changed!");
-                        BoardState::SleepValidate(board)
-                    }
-                }
-            }
-            BoardState::Ready(board) => {
-                print!("Solving board...");
-                stdout().flush().unwrap();
-                state = match board.solve(Duration::from_secs(5)) {
-                    SolveResult::Solution(solution) => {
-                        println!(" Done!");
-                        BoardState::Solve(solution)
-                    }
-                    SolveResult::Timeout => {
-                        println!(" Timeout!");
-                        println!("Skipping to next game.");
-                        mouse::click_next_game(&mouse);
-                        BoardState::SleepSearch
-                    }
-                    SolveResult::Unsolvable => {
-                        println!(" Unsolvable!");
-                        BoardState::SleepUnsolvable(board)
-                    }
-                };
-            }
-            BoardState::Solve(steps) => {
-                print!("Applying solution in-game...");
-                stdout().flush().unwrap();
+impl Strategy {
+    fn name(self) -> &'static str {
+        match self {
+            Self::DepthFirst => "depth-first",
+            Self::BestFirst => "best-first",
+            Self::Restarts => "restarts",
+        }
+    }
 
-                for Step([step1, step2]) in steps {
-                    mouse::click_at_coord(&mouse, step1);
-                    if step2 != step1 {
-                        mouse::click_at_coord(&mouse, step2);
-                    }
-                }
+    fn solver(self) -> Box<dyn Solver> {
+        match self {
+            Self::DepthFirst => Box::new(DepthFirst),
+            Self::BestFirst => Box::new(BestFirst::default()),
+            Self::Restarts => Box::new(Restarts::default()),
+        }
+    }
+}
 
-                mouse::click_next_game(&mouse);
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Prints the number of boards and how many of them are solved or unsolvable.
+    Stats,
+    /// Prints each board in compact notation with its outcome, oldest first.
+    List,
+    /// Drops the oldest boards until at most the given number are left.
+    Prune {
+        /// Number of boards to keep.
+        keep: usize,
+    },
+    /// Removes all boards.
+    Clear,
+}
 
-                println!(" Done!");
+/// Parses a timeout in seconds, which may be fractional.
+fn parse_timeout(seconds: &str) -> Result<Duration, String> {
+    let seconds = seconds.parse::<f64>().map_err(|error| error.to_string())?;
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| format!("`{seconds}` is not a non-negative number of seconds"))
+}
 
-                state = BoardState::SleepSearch;
-            }
-            BoardState::Unsolvable(unsolvable_board) => {
-                let image = capture_first_screen();
+fn main() -> ExitCode {
+    let cli = Cli::parse();
 
-                state = match scanner.scan_image(&image) {
-                    Some(board) if board == unsolvable_board => BoardState::SleepUnsolvable(board),
-                    Some(board) => {
-                        println!("Found a valid board!");
-                        BoardState::SleepValidate(board)
-                    }
-                    _ => BoardState::SleepSearch,
-                };
-            }
-        }
-    }
-}
+    let result = match cli.command.unwrap_or(Command::Play {
+        cache: DEFAULT_CACHE_FILE.into(),
+        no_cache: false,
+        layout: DEFAULT_LAYOUT.into(),
+    }) {
+        Command::Play {
+            cache,
+            no_cache,
+            layout,
+        } => commands::layout(&layout)
+            .and_then(|layout| play::play((!no_cache).then_some(cache), &layout)),
+        Command::Solve {
+            file,
+            timeout,
+            stats,
+            threads,
+            cache,
+            strategy,
+        } => commands::solve(
+            file.as_deref(),
+            SolveOptions {
+                timeout,
+                threads: commands::thread_count(threads),
+                ..SolveOptions::default()
+            },
+            stats,
+            cache.as_deref(),
+            strategy,
+        ),
+        Command::Hint {
+            file,
+            screen,
+            layout,
+            timeout,
+        } => commands::layout(&layout).and_then(|layout| {
+            commands::hint(
+                file.as_deref(),
+                screen.then_some(&layout),
+                SolveOptions {
+                    timeout,
+                    ..SolveOptions::default()
+                },
+            )
+        }),
+        Command::Rate {
+            file,
+            timeout,
+            threads,
+            json,
+        } => commands::rate(
+            file.as_deref(),
+            SolveOptions {
+                timeout,
+                threads: commands::thread_count(threads),
+                ..SolveOptions::default()
+            },
+            json,
+        ),
+        Command::Solutions {
+            file,
+            limit,
+            timeout,
+            list,
+        } => commands::solutions(file.as_deref(), limit, timeout, list),
+        Command::Verify { board, steps } => commands::verify(&board, steps.as_deref()),
+        Command::Cache { file, action } => commands::cache(&file, action),
+        Command::Generate {
+            seed,
+            count,
+            solvable,
+            compact,
+        } => commands::generate(seed, count, solvable, compact),
+        Command::Bench {
+            seed,
+            count,
+            solvable,
+            timeout,
+            threads,
+            strategy,
+            no_salt_pruning,
+            no_vitae_mors_pruning,
+            no_metal_pruning,
+            json,
+        } => bench::bench(
+            seed,
+            count,
+            solvable,
+            SolveOptions {
+                timeout,
+                threads: commands::thread_count(threads),
+                pruning: Pruning {
+                    salt: !no_salt_pruning,
+                    vitae_mors: !no_vitae_mors_pruning,
+                    metals: !no_metal_pruning,
+                },
+            },
+            &strategy,
+            json,
+        ),
+    };
 
-fn capture_first_screen() -> RgbImage {
-    // image::open(".example.png").unwrap().into_rgb8()
-    let screens = Screen::all().unwrap();
-    let screen = screens.first().expect("no screen");
-    DynamicImage::from(screen.capture().unwrap()).to_rgb8()
+    result.unwrap_or_else(|error| {
+        eprintln!("error: {error}");
+        ExitCode::FAILURE
+    })
 }
@@ -2,19 +2,34 @@ use std::{thread::sleep, time::Duration};
 
 use mouse_rs::{types::keys::Keys, Mouse};
 
-use crate::{index::SigCoord, screen::coord_to_screen};
+use sigmars_garden_solver::{Geometry, Layout, SigCoord};
 
-pub(crate) fn click_next_game(mouse: &Mouse) {
-    click_at(mouse, 870, 886);
+// Positions are relative to the monitor, whose top left corner is at `origin` on the desktop.
+
+pub(crate) fn click_next_game(
+    mouse: &Mouse,
+    origin: (i32, i32),
+    layout: &Layout,
+    geometry: Geometry,
+) {
+    let (x, y) = layout.new_game_button(geometry);
+    click_at(mouse, origin, x, y);
 }
 
-pub(crate) fn click_at_coord(mouse: &Mouse, coord: impl Into<SigCoord>) {
-    let (x, y) = coord_to_screen(coord);
-    click_at(mouse, x, y);
+pub(crate) fn click_at_coord(
+    mouse: &Mouse,
+    origin: (i32, i32),
+    geometry: Geometry,
+    coord: impl Into<SigCoord>,
+) {
+    let (x, y) = geometry.coord_to_screen(coord);
+    click_at(mouse, origin, x, y);
 }
 
-pub(crate) fn click_at(mouse: &Mouse, x: u32, y: u32) {
-    mouse.move_to(x as i32, y as i32).unwrap();
+pub(crate) fn click_at(mouse: &Mouse, (origin_x, origin_y): (i32, i32), x: u32, y: u32) {
+    mouse
+        .move_to(origin_x + x as i32, origin_y + y as i32)
+        .unwrap();
     sleep(Duration::from_millis(42));
     mouse.press(&Keys::LEFT).unwrap();
     sleep(Duration::from_millis(42));
@@ -0,0 +1,263 @@
+//! A human-readable text notation for boards.
+//!
+//! Each position is written as the [symbol](Element::symbol) of its element, or `.` if it is
+//! empty. Positions are listed row by row from the top (row -5) to the bottom (row 5), which is the
+//! same order as [`SigIndex::all`].
+//!
+//! The regular form (`{}`) writes one row per line and indents them to resemble the hexagonal
+//! playing field:
+//!
+//! ```text
+//!      . . . . . .
+//!     . . . . . . .
+//!    . . . . . . . .
+//!   . . . . . . . . .
+//!  . . . . . . . . . .
+//! . . . . . G . . . . .
+//!  . . . . . . . . . .
+//!   . . . . . . . . .
+//!    . . . . . . . .
+//!     . . . . . . .
+//!      . . . . . .
+//! ```
+//!
+//! The compact form (`{:#}`) fits on a single line by separating rows with `/` instead:
+//!
+//! ```text
+//! ....../......./......../........./........../.....G...../........../........./......../......./......
+//! ```
+//!
+//! When parsing, whitespace and `/` are ignored, so both forms are accepted.
+//!
+//! A [`SigCoord`] is written as `row,col`, e.g. `-5,-3`, and a [`Step`] as its two coordinates
+//! separated by a space, e.g. `-5,-3 0,1`.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::{
+    board::{Board, Step},
+    element::Element,
+    index::{SigCoord, SigIndex},
+};
+
+const EMPTY_SYMBOL: char = '.';
+
+impl Display for Board {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut last_row = None;
+        for index in SigIndex::all() {
+            let row = SigCoord::from(index).row();
+            if last_row != Some(row) {
+                if last_row.is_some() {
+                    if f.alternate() {
+                        write!(f, "/")?;
+                    } else {
+                        writeln!(f)?;
+                    }
+                }
+                if !f.alternate() {
+                    write!(f, "{:1$}", "", row.unsigned_abs() as usize)?;
+                }
+                last_row = Some(row);
+            } else if !f.alternate() {
+                write!(f, " ")?;
+            }
+            write!(
+                f,
+                "{}",
+                self.get(index).map_or(EMPTY_SYMBOL, Element::symbol)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Board {
+    type Err = ParseBoardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut board = Board::new();
+        let mut indices = SigIndex::all();
+        let mut count = 0;
+        for symbol in s.chars().filter(|&c| !c.is_whitespace() && c != '/') {
+            let element = match symbol {
+                EMPTY_SYMBOL => None,
+                _ => Some(
+                    Element::from_symbol(symbol).ok_or(ParseBoardError::InvalidSymbol(symbol))?,
+                ),
+            };
+            if let Some(index) = indices.next() {
+                board.set(index, element);
+            }
+            count += 1;
+        }
+        if count == SigIndex::all().len() {
+            Ok(board)
+        } else {
+            Err(ParseBoardError::WrongPositionCount(count))
+        }
+    }
+}
+
+/// An error when parsing a [`Board`] from its text notation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseBoardError {
+    /// A character that is neither an element symbol nor `.`.
+    InvalidSymbol(char),
+    /// The number of positions found, which should have been 91.
+    WrongPositionCount(usize),
+}
+
+impl Display for ParseBoardError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidSymbol(symbol) => write!(f, "invalid symbol {symbol:?}"),
+            Self::WrongPositionCount(count) => write!(f, "expected 91 positions, found {count}"),
+        }
+    }
+}
+
+impl Error for ParseBoardError {}
+
+impl Display for SigCoord {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{},{}", self.row(), self.col())
+    }
+}
+
+impl FromStr for SigCoord {
+    type Err = ParseStepError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_once(',')
+            .and_then(|(row, col)| {
+                SigCoord::new(row.trim().parse().ok()?, col.trim().parse().ok()?)
+            })
+            .ok_or_else(|| ParseStepError::InvalidCoord(s.to_string()))
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let [index1, index2] = self.0.map(SigCoord::from);
+        write!(f, "{index1} {index2}")
+    }
+}
+
+impl FromStr for Step {
+    type Err = ParseStepError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let coords = s
+            .split_whitespace()
+            .map(|coord| coord.parse::<SigCoord>().map(SigIndex::from))
+            .collect::<Result<Vec<_>, _>>()?;
+        let count = coords.len();
+        coords
+            .try_into()
+            .map(Step)
+            .map_err(|_| ParseStepError::WrongCoordCount(count))
+    }
+}
+
+/// An error when parsing a [`SigCoord`] or [`Step`] from its text notation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseStepError {
+    /// Not of the form `row,col` or outside of the playing field.
+    InvalidCoord(String),
+    /// The number of coordinates found, which should have been 2.
+    WrongCoordCount(usize),
+}
+
+impl Display for ParseStepError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidCoord(coord) => write!(f, "invalid coordinate {coord:?}"),
+            Self::WrongCoordCount(count) => write!(f, "expected 2 coordinates, found {count}"),
+        }
+    }
+}
+
+impl Error for ParseStepError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_board() -> Board {
+        let mut board = Board::new();
+        for (index, element_index) in SigIndex::all().zip((0..15).cycle()) {
+            board.set(index, Element::from_index(element_index));
+        }
+        board
+    }
+
+    #[test]
+    fn round_trip() {
+        let board = example_board();
+        assert_eq!(board.to_string().parse(), Ok(board));
+        assert_eq!(format!("{board:#}").parse(), Ok(board));
+    }
+
+    #[test]
+    fn layout() {
+        let text = Board::new().to_string();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], "     . . . . . .");
+        assert_eq!(lines[5], ". . . . . . . . . . .");
+        assert_eq!(lines[10], "     . . . . . .");
+
+        let compact = format!("{:#}", Board::new());
+        assert_eq!(compact.split('/').count(), 11);
+        assert!(!compact.contains(char::is_whitespace));
+    }
+
+    #[test]
+    fn step_round_trip() {
+        for index1 in SigIndex::all() {
+            for index2 in SigIndex::all() {
+                let step = Step([index1, index2]);
+                assert_eq!(step.to_string().parse(), Ok(step));
+            }
+        }
+        assert_eq!(
+            "-5,-3 0,1"
+                .parse::<Step>()
+                .map(|step| step.0.map(SigCoord::from)),
+            Ok([SigCoord::new(-5, -3).unwrap(), SigCoord::new(0, 1).unwrap()])
+        );
+    }
+
+    #[test]
+    fn step_errors() {
+        assert_eq!(
+            "-5,3 0,0".parse::<Step>(),
+            Err(ParseStepError::InvalidCoord("-5,3".to_string()))
+        );
+        assert_eq!(
+            "0,0".parse::<Step>(),
+            Err(ParseStepError::WrongCoordCount(1))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            "x".repeat(91).parse::<Board>(),
+            Err(ParseBoardError::InvalidSymbol('x'))
+        );
+        assert_eq!(
+            ".".repeat(90).parse::<Board>(),
+            Err(ParseBoardError::WrongPositionCount(90))
+        );
+        assert_eq!(
+            ".".repeat(92).parse::<Board>(),
+            Err(ParseBoardError::WrongPositionCount(92))
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Solving a board with several threads at once.
+//!
+//! The first few levels of the search tree are expanded up front, and the resulting positions are
+//! handed out to worker threads one by one, most promising first. All workers share the table of
+//! dead positions, so a subtree that was already exhausted by one thread is skipped by the others.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+use crate::{
+    bitboard::BitBoard,
+    board::Step,
+    rules::RuleSet,
+    search::{Budget, DeadPositions, Search, SearchControl, SearchStatus, DEAD_POSITIONS_MEMORY},
+    solver::{GamePosition, SolveOptions, SolveResult, SolveStats},
+};
+
+/// How many positions to expand up front per thread, so that threads which happen to get easy
+/// subtrees can pick up more work.
+const TASKS_PER_THREAD: usize = 16;
+
+pub(crate) fn solve(position: GamePosition, options: SolveOptions) -> (SolveResult, SolveStats) {
+    let SolveOptions {
+        timeout,
+        threads,
+        pruning,
+    } = options;
+    let rules = position.rules();
+    let start = Instant::now();
+    let initial_board = BitBoard::from(position.board());
+    let mut stats = SolveStats::default();
+
+    let tasks = match split(
+        initial_board,
+        &rules,
+        threads * TASKS_PER_THREAD,
+        &mut stats,
+    ) {
+        Ok(tasks) => tasks,
+        Err(result) => {
+            stats.elapsed = start.elapsed();
+            return (result, stats);
+        }
+    };
+
+    let next_task = AtomicUsize::new(0);
+    let timed_out = AtomicBool::new(false);
+    let solution = Mutex::new(None);
+    let dead_positions = Arc::new(DeadPositions::with_memory(DEAD_POSITIONS_MEMORY));
+    let control = SearchControl::default();
+    let worker_stats = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                let mut stats = SolveStats::default();
+                while let Some(steps) = tasks.get(next_task.fetch_add(1, Ordering::Relaxed)) {
+                    let mut search = Search::below(
+                        initial_board,
+                        rules,
+                        steps.clone(),
+                        dead_positions.clone(),
+                        control.clone(),
+                    )
+                    .pruning(pruning);
+                    let status = search.run(Budget::Time(timeout.saturating_sub(start.elapsed())));
+                    stats.merge(search.stats());
+                    match status {
+                        SearchStatus::Solved(steps) => {
+                            *solution.lock().unwrap() = Some(steps);
+                            control.cancel();
+                        }
+                        SearchStatus::Unsolvable => continue,
+                        SearchStatus::Paused => {
+                            timed_out.store(true, Ordering::Relaxed);
+                            control.cancel();
+                        }
+                        SearchStatus::Cancelled => {}
+                    }
+                    break;
+                }
+                worker_stats.lock().unwrap().push(stats);
+            });
+        }
+    });
+
+    for worker_stats in worker_stats.into_inner().unwrap() {
+        stats.merge(&worker_stats);
+    }
+    stats.elapsed = start.elapsed();
+
+    let result = if let Some(steps) = solution.into_inner().unwrap() {
+        SolveResult::Solution(steps)
+    } else if timed_out.into_inner() {
+        SolveResult::Timeout
+    } else {
+        SolveResult::Unsolvable
+    };
+    (result, stats)
+}
+
+/// Expands the search tree breadth-first until there are at least `count` positions to search,
+/// returning the steps leading to each of them, most promising first.
+///
+/// Returns the result directly if it is found while splitting.
+fn split(
+    initial_board: BitBoard,
+    rules: &RuleSet,
+    count: usize,
+    stats: &mut SolveStats,
+) -> Result<Vec<Vec<Step>>, SolveResult> {
+    if initial_board.is_solved() {
+        return Err(SolveResult::Solution(Vec::new()));
+    }
+
+    let mut tasks = vec![Vec::<Step>::new()];
+    while tasks.len() < count {
+        let mut next_tasks = Vec::new();
+        for steps in tasks {
+            let mut board = initial_board;
+            for step in &steps {
+                board.remove(step.0[0]);
+                board.remove(step.0[1]);
+            }
+            let valid_steps = board.valid_steps(rules);
+            stats.expanded(steps.len(), valid_steps.len());
+
+            // The most promising steps are last, since the sequential search pops them first.
+            for step in valid_steps.into_iter().rev() {
+                let mut steps = steps.clone();
+                steps.push(step);
+                let mut board = board;
+                board.remove(step.0[0]);
+                board.remove(step.0[1]);
+                if board.is_solved() {
+                    return Err(SolveResult::Solution(steps));
+                }
+                next_tasks.push(steps);
+            }
+        }
+        if next_tasks.is_empty() {
+            return Err(SolveResult::Unsolvable);
+        }
+        tasks = next_tasks;
+    }
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::generator::Generator;
+
+    use super::*;
+
+    #[test]
+    fn same_outcome_as_sequential() {
+        let mut generator = Generator::new(4);
+        for _ in 0..20 {
+            let board = generator.generate();
+            let sequential = board.solve(Duration::from_millis(100));
+            if sequential == SolveResult::Timeout {
+                continue;
+            }
+            let options = SolveOptions {
+                timeout: Duration::from_secs(60),
+                threads: 2,
+                ..SolveOptions::default()
+            };
+            let (parallel, _) = solve(board.into(), options);
+            match sequential {
+                SolveResult::Solution(_) => assert!(matches!(parallel, SolveResult::Solution(_))),
+                _ => assert_eq!(parallel, sequential),
+            }
+        }
+    }
+}
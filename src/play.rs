@@ -0,0 +1,295 @@
+use std::{
+    error::Error,
+    io::{stdout, Write},
+    path::PathBuf,
+    process::ExitCode,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use image::{DynamicImage, RgbImage};
+use mouse_rs::Mouse;
+use screenshots::Screen;
+use sigmars_garden_solver::{
+    verify, GamePosition, Geometry, Layout, Scanner, Search, SearchHandle, SearchStatus,
+    SolutionCache, SolveResult, Step,
+};
+
+use crate::mouse;
+
+/// How long to search for a solution before skipping to the next game.
+const MAX_SEARCH_TIME: Duration = Duration::from_secs(5);
+
+/// How often to print the progress of the search.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often to locate the board again while no game was found where it was located.
+const LOCATE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The solver already starts searching while the board is being validated, so its handle is
+/// passed along with the board.
+enum BoardState {
+    SleepSearch,
+    SleepValidate(GamePosition, SearchHandle),
+    SleepUnsolvable(GamePosition),
+    Search,
+    Validate(GamePosition, SearchHandle),
+    Ready(GamePosition, SearchHandle),
+    Solve(GamePosition, Vec<Step>),
+    Unsolvable(GamePosition),
+}
+
+/// Plays the game indefinitely by scanning the screen and clicking the solution.
+///
+/// Outcomes are looked up in and added to the cache at `cache_path`, if given.
+pub(crate) fn play(
+    cache_path: Option<PathBuf>,
+    layout: &Layout,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let mouse = Mouse::new();
+    let origin = screen_origin(layout.monitor)?;
+    layout.check(capture_screen(layout.monitor)?.dimensions())?;
+    let scanner = scanner(layout);
+    let mut location = BoardLocation::new(layout);
+    let mut cache = cache_path.map(SolutionCache::open).transpose()?;
+
+    let mut state = BoardState::Search;
+    loop {
+        match state {
+            BoardState::SleepSearch => {
+                sleep(Duration::from_secs(1));
+                state = BoardState::Search;
+            }
+            BoardState::SleepValidate(board, search) => {
+                print!("Making sure nothing is moving...");
+                stdout().flush().unwrap();
+                sleep(Duration::from_secs(2));
+                state = BoardState::Validate(board, search);
+            }
+            BoardState::SleepUnsolvable(board) => {
+                sleep(Duration::from_secs(1));
+                state = BoardState::Unsolvable(board);
+            }
+            BoardState::Search => {
+                state = match scan_screen(&scanner, layout.monitor, &mut location)? {
+                    Some(board) => {
+                        println!("Found a valid board!");
+                        BoardState::SleepValidate(board, Search::new(board).spawn())
+                    }
+                    None => BoardState::SleepSearch,
+                };
+            }
+            BoardState::Validate(board, search) => {
+                state = match scan_screen(&scanner, layout.monitor, &mut location)? {
+                    Some(confirmation_board) if board == confirmation_board => {
+                        println!(" Ready!");
+                        BoardState::Ready(board, search)
+                    }
+                    Some(new_board) => {
+                        println!(" Board changed!");
+                        search.control().cancel();
+                        BoardState::SleepValidate(new_board, Search::new(new_board).spawn())
+                    }
+                    None => {
+                        println!(" Board changed!");
+                        BoardState::SleepValidate(board, search)
+                    }
+                }
+            }
+            BoardState::Ready(board, search) => {
+                if let Some(result) = cache.as_ref().and_then(|cache| cache.get(board.board())) {
+                    search.control().cancel();
+                    state = match result {
+                        SolveResult::Solution(solution) => {
+                            println!("Found solution in cache!");
+                            BoardState::Solve(board, solution)
+                        }
+                        _ => {
+                            println!("Known to be unsolvable!");
+                            BoardState::SleepUnsolvable(board)
+                        }
+                    };
+                    continue;
+                }
+
+                print!("Solving board...");
+                stdout().flush().unwrap();
+                while !search.is_finished() {
+                    let stats = search.stats();
+                    print!(
+                        "\rSolving board... {:.1}s, {} positions, {} backtracks",
+                        stats.elapsed.as_secs_f64(),
+                        stats.nodes,
+                        stats.backtracks
+                    );
+                    stdout().flush().unwrap();
+                    if stats.elapsed >= MAX_SEARCH_TIME {
+                        search.control().cancel();
+                    }
+                    sleep(PROGRESS_INTERVAL);
+                }
+                let status = search.join().0;
+                if let Some(cache) = &mut cache {
+                    let result = match &status {
+                        SearchStatus::Solved(solution) => SolveResult::Solution(solution.clone()),
+                        SearchStatus::Unsolvable => SolveResult::Unsolvable,
+                        SearchStatus::Paused | SearchStatus::Cancelled => SolveResult::Timeout,
+                    };
+                    cache.insert(board.board(), &result);
+                    if let Err(error) = cache.save() {
+                        eprintln!("Could not save cache: {error}");
+                    }
+                }
+                state = match status {
+                    SearchStatus::Solved(solution) => {
+                        println!(" Done!");
+                        BoardState::Solve(board, solution)
+                    }
+                    SearchStatus::Paused | SearchStatus::Cancelled => {
+                        println!(" Timeout!");
+                        println!("Skipping to next game.");
+                        mouse::click_next_game(&mouse, origin, layout, location.geometry);
+                        BoardState::SleepSearch
+                    }
+                    SearchStatus::Unsolvable => {
+                        println!(" Unsolvable!");
+                        BoardState::SleepUnsolvable(board)
+                    }
+                };
+            }
+            BoardState::Solve(board, steps) => {
+                if let Err(error) = verify(board.board(), &steps) {
+                    println!("Solution is invalid: {error}");
+                    println!("Skipping to next game.");
+                    mouse::click_next_game(&mouse, origin, layout, location.geometry);
+                    state = BoardState::SleepSearch;
+                    continue;
+                }
+
+                print!("Applying solution in-game...");
+                stdout().flush().unwrap();
+
+                for Step([step1, step2]) in steps {
+                    mouse::click_at_coord(&mouse, origin, location.geometry, step1);
+                    if step2 != step1 {
+                        mouse::click_at_coord(&mouse, origin, location.geometry, step2);
+                    }
+                }
+
+                mouse::click_next_game(&mouse, origin, layout, location.geometry);
+
+                println!(" Done!");
+
+                state = BoardState::SleepSearch;
+            }
+            BoardState::Unsolvable(unsolvable_board) => {
+                state = match scan_screen(&scanner, layout.monitor, &mut location)? {
+                    Some(board) if board == unsolvable_board => BoardState::SleepUnsolvable(board),
+                    Some(board) => {
+                        println!("Found a valid board!");
+                        BoardState::SleepValidate(board, Search::new(board).spawn())
+                    }
+                    _ => BoardState::SleepSearch,
+                };
+            }
+        }
+    }
+}
+
+/// A scanner at the board geometry of the layout, or locating the board if it has none.
+pub(crate) fn scanner(layout: &Layout) -> Scanner {
+    match layout.board {
+        Some(geometry) => Scanner::new().geometry(geometry),
+        None => Scanner::new(),
+    }
+}
+
+/// Where the board was last found, for scanning and clicking on it.
+///
+/// Locating the board takes much longer than scanning it, so it is located once for each size of
+/// the screen. Until a game is found at the located geometry, which may be wrong if the board was
+/// located during an animation, it is located again every [`LOCATE_INTERVAL`].
+struct BoardLocation {
+    geometry: Geometry,
+    /// Whether the geometry is fixed by the layout and never located.
+    fixed: bool,
+    /// The size of the screenshot the board was last located in, or [`None`] if it was not
+    /// located yet.
+    image_size: Option<(u32, u32)>,
+    /// When the board was last located.
+    located_at: Option<Instant>,
+    /// Whether a game was found at the geometry, which shows it is right.
+    confirmed: bool,
+}
+
+impl BoardLocation {
+    fn new(layout: &Layout) -> Self {
+        Self {
+            geometry: layout.board.unwrap_or(Geometry::DEFAULT),
+            fixed: layout.board.is_some(),
+            image_size: None,
+            located_at: None,
+            confirmed: false,
+        }
+    }
+
+    /// Whether the board has to be located in a screenshot of the given size before scanning it.
+    fn needs_locating(&self, image_size: (u32, u32)) -> bool {
+        if self.fixed {
+            false
+        } else if self.image_size != Some(image_size) {
+            true
+        } else {
+            !self.confirmed
+                && self
+                    .located_at
+                    .is_none_or(|located_at| located_at.elapsed() >= LOCATE_INTERVAL)
+        }
+    }
+
+    fn locate(&mut self, scanner: &Scanner, image: &RgbImage) {
+        // A failed attempt still counts, so the board is not located over and over while there
+        // is no game on the screen.
+        if let Some(geometry) = scanner.locate(image) {
+            self.geometry = geometry;
+        }
+        self.confirmed = false;
+        self.image_size = Some(image.dimensions());
+        self.located_at = Some(Instant::now());
+    }
+}
+
+/// Captures and scans the monitor, keeping track of where the board was found.
+fn scan_screen(
+    scanner: &Scanner,
+    monitor: usize,
+    location: &mut BoardLocation,
+) -> Result<Option<GamePosition>, Box<dyn Error>> {
+    let image = capture_screen(monitor)?;
+    if location.needs_locating(image.dimensions()) {
+        location.locate(scanner, &image);
+    }
+    let position = scanner.scan_game_position_at(&image, location.geometry);
+    location.confirmed |= position.is_some();
+    Ok(position)
+}
+
+fn find_screen(monitor: usize) -> Result<Screen, Box<dyn Error>> {
+    let mut screens = Screen::all()?;
+    if monitor >= screens.len() {
+        return Err(format!("no monitor {monitor}, there are {}", screens.len()).into());
+    }
+    Ok(screens.swap_remove(monitor))
+}
+
+/// The position of the top left corner of the monitor on the desktop.
+fn screen_origin(monitor: usize) -> Result<(i32, i32), Box<dyn Error>> {
+    let info = find_screen(monitor)?.display_info;
+    Ok((info.x, info.y))
+}
+
+pub(crate) fn capture_screen(monitor: usize) -> Result<RgbImage, Box<dyn Error>> {
+    // return Ok(image::open(".example.png").unwrap().into_rgb8());
+    let image = find_screen(monitor)?.capture()?;
+    Ok(DynamicImage::from(image).to_rgb8())
+}
@@ -0,0 +1,154 @@
+use crate::{
+    bitboard::{free_mask, BitBoard},
+    element::Element,
+    rules::RuleSet,
+};
+
+/// Cheap checks that prove a position can no longer be cleared, so the search can skip it.
+///
+/// Each check can be turned off on its own, e.g. to measure how much it helps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pruning {
+    /// Every cardinal element with an odd count needs a salt, and the remaining salts need to pair
+    /// up with each other.
+    pub salt: bool,
+    /// Vitae and mors only pair with each other, so there must be as many of each.
+    pub vitae_mors: bool,
+    /// Each metal needs a quicksilver, and must have three consecutive neighbors that can be
+    /// emptied before the higher metals and gold are removed.
+    pub metals: bool,
+}
+
+impl Pruning {
+    /// All checks, which is the default.
+    pub const ALL: Self = Self {
+        salt: true,
+        vitae_mors: true,
+        metals: true,
+    };
+
+    /// No checks, e.g. to measure how much they help.
+    pub const NONE: Self = Self {
+        salt: false,
+        vitae_mors: false,
+        metals: false,
+    };
+
+    /// Whether the position passes all enabled checks.
+    ///
+    /// A position that fails a check can never be cleared, while passing them does not guarantee
+    /// anything.
+    ///
+    /// The checks only hold for the [standard rules](RuleSet::STANDARD), so positions under other
+    /// rules always pass.
+    pub(crate) fn is_feasible(self, board: &BitBoard, rules: &RuleSet) -> bool {
+        if *rules != RuleSet::STANDARD {
+            return true;
+        }
+
+        let count = |element| board.mask(element).count_ones();
+
+        if self.salt {
+            let odd_cardinals = Element::CARDINALS
+                .into_iter()
+                .filter(|&element| count(element) % 2 == 1)
+                .count() as u32;
+            let salt = count(Element::Salt);
+            if odd_cardinals > salt || (salt - odd_cardinals) % 2 == 1 {
+                return false;
+            }
+        }
+
+        if self.vitae_mors && count(Element::Vitae) != count(Element::Mors) {
+            return false;
+        }
+
+        if self.metals {
+            let metals = Element::METALS.into_iter().map(count).sum::<u32>();
+            if metals != count(Element::Quicksilver) {
+                return false;
+            }
+
+            // Going from the highest metal down, collects everything that has to stay in place
+            // until the current metal is removed.
+            let mut blocking = board.mask(Element::Gold);
+            for metal in Element::METALS.into_iter().rev() {
+                let mask = board.mask(metal);
+                if free_mask(blocking | mask, rules.free_neighbors) & mask != mask {
+                    return false;
+                }
+                blocking |= mask;
+            }
+        }
+
+        true
+    }
+}
+
+impl Default for Pruning {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+
+    use super::*;
+
+    fn board(elements: &[(i8, i8, Element)]) -> BitBoard {
+        BitBoard::from(Board::with_elements(elements))
+    }
+
+    #[test]
+    fn salt_parity() {
+        let feasible = board(&[(0, 0, Element::Salt), (1, 1, Element::Fire)]);
+        assert!(Pruning::ALL.is_feasible(&feasible, &RuleSet::STANDARD));
+
+        let missing_salt = board(&[(0, 0, Element::Water), (1, 1, Element::Fire)]);
+        assert!(!Pruning::ALL.is_feasible(&missing_salt, &RuleSet::STANDARD));
+        assert!(Pruning::NONE.is_feasible(&missing_salt, &RuleSet::STANDARD));
+
+        let odd_salt = board(&[(0, 0, Element::Salt), (1, 1, Element::Salt)]);
+        assert!(Pruning::ALL.is_feasible(&odd_salt, &RuleSet::STANDARD));
+        let odd_salt = board(&[
+            (0, 0, Element::Salt),
+            (2, 2, Element::Salt),
+            (1, 1, Element::Salt),
+        ]);
+        assert!(!Pruning::ALL.is_feasible(&odd_salt, &RuleSet::STANDARD));
+    }
+
+    #[test]
+    fn vitae_mors() {
+        let unpaired = board(&[(0, 0, Element::Vitae), (1, 1, Element::Vitae)]);
+        assert!(!Pruning::ALL.is_feasible(&unpaired, &RuleSet::STANDARD));
+        assert!(Pruning {
+            vitae_mors: false,
+            ..Pruning::ALL
+        }
+        .is_feasible(&unpaired, &RuleSet::STANDARD));
+    }
+
+    #[test]
+    fn buried_metal() {
+        // Lead in the center can be freed as long as one of its neighbors that lie between tin
+        // and gold can be emptied first.
+        let mut elements = [
+            (0, 0, Element::Lead),
+            (0, 1, Element::Gold),
+            (1, 0, Element::Tin),
+            (-1, -1, Element::Fire),
+            (-5, -5, Element::Fire),
+            (5, 5, Element::Quicksilver),
+            (5, 4, Element::Quicksilver),
+        ];
+        assert!(Pruning::ALL.is_feasible(&board(&elements), &RuleSet::STANDARD));
+
+        elements[3].2 = Element::Iron;
+        elements[4].2 = Element::Quicksilver;
+        assert!(!Pruning::ALL.is_feasible(&board(&elements), &RuleSet::STANDARD));
+        assert!(Pruning::NONE.is_feasible(&board(&elements), &RuleSet::STANDARD));
+    }
+}
@@ -0,0 +1,135 @@
+//! Restarting the search with a different order of steps to break heavy-tailed solve times.
+//!
+//! Most boards are solved right away, but an unlucky early step can send the depth-first search
+//! into a huge subtree without a solution. Instead of searching it to the end, the search is
+//! restarted after a while with ties between steps broken differently, following the Luby sequence
+//! of budgets 1, 1, 2, 1, 1, 2, 4, … times a unit, which is within a logarithmic factor of the best
+//! fixed restart budget.
+//!
+//! All runs share the table of dead positions, since a position without a solution has none no
+//! matter in which order it is searched, so later runs skip what earlier ones already explored.
+
+use std::{sync::Arc, time::Instant};
+
+use crate::{
+    bitboard::BitBoard,
+    search::{Budget, DeadPositions, Search, SearchControl, SearchStatus, DEAD_POSITIONS_MEMORY},
+    solver::{GamePosition, SolveOptions, SolveResult, SolveStats, Solver},
+};
+
+/// How many iterations to run between checking the timeout.
+const TIMEOUT_CHECK_INTERVAL: u64 = 4096;
+
+/// Runs randomized depth-first searches, restarting them according to the Luby sequence.
+///
+/// Runs on a single thread, ignoring [`SolveOptions::threads`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Restarts {
+    /// The seed for the order of the first run, incremented for each restart.
+    pub seed: u64,
+    /// The number of iterations that each entry of the Luby sequence stands for.
+    pub unit: u64,
+}
+
+impl Default for Restarts {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            unit: 10_000,
+        }
+    }
+}
+
+impl Solver for Restarts {
+    fn solve(&self, position: GamePosition, options: SolveOptions) -> (SolveResult, SolveStats) {
+        let start = Instant::now();
+        let initial_board = BitBoard::from(position.board());
+        let dead_positions = Arc::new(DeadPositions::with_memory(DEAD_POSITIONS_MEMORY));
+        let mut stats = SolveStats::default();
+
+        let mut run = 0;
+        let result = 'restarts: loop {
+            let mut search = Search::below(
+                initial_board,
+                position.rules(),
+                Vec::new(),
+                dead_positions.clone(),
+                SearchControl::default(),
+            )
+            .pruning(options.pruning)
+            .randomize(self.seed.wrapping_add(run));
+
+            let mut budget = self.unit.saturating_mul(luby(run + 1));
+            let status = loop {
+                if start.elapsed() >= options.timeout {
+                    stats.merge(search.stats());
+                    break 'restarts SolveResult::Timeout;
+                }
+                if budget == 0 {
+                    break SearchStatus::Paused;
+                }
+                let iterations = budget.min(TIMEOUT_CHECK_INTERVAL);
+                budget -= iterations;
+                match search.run(Budget::Iterations(iterations)) {
+                    SearchStatus::Paused => {}
+                    status => break status,
+                }
+            };
+
+            stats.merge(search.stats());
+            match status {
+                SearchStatus::Solved(steps) => break SolveResult::Solution(steps),
+                SearchStatus::Unsolvable => break SolveResult::Unsolvable,
+                SearchStatus::Paused | SearchStatus::Cancelled => run += 1,
+            }
+        };
+
+        stats.elapsed = start.elapsed();
+        (result, stats)
+    }
+}
+
+/// The `i`-th entry of the Luby sequence, counting from 1.
+fn luby(mut i: u64) -> u64 {
+    loop {
+        // The largest power of two that is at most `i`.
+        let power = 1 << (u64::BITS - 1 - i.leading_zeros());
+        if i == 2 * power - 1 {
+            return power;
+        }
+        i -= power - 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{generator::Generator, verify::verify};
+
+    use super::*;
+
+    #[test]
+    fn luby_sequence() {
+        let sequence = (1..=15).map(luby).collect::<Vec<_>>();
+        assert_eq!(sequence, [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn restarts_find_valid_solutions() {
+        let mut generator = Generator::new(8).solvable(true);
+        let options = SolveOptions {
+            timeout: Duration::from_secs(60),
+            ..SolveOptions::default()
+        };
+        let restarts = Restarts { seed: 0, unit: 10 };
+        for _ in 0..5 {
+            let board = generator.generate();
+            let (result, _) = restarts.solve(board.into(), options);
+            let SolveResult::Solution(steps) = result else {
+                panic!("generated board should be solvable");
+            };
+            assert_eq!(verify(board.board(), &steps), Ok(()));
+        }
+    }
+}
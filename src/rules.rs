@@ -0,0 +1,309 @@
+//! The rules deciding which elements can be removed and when.
+
+use crate::{
+    bitboard::BitBoard,
+    board::{Board, Step, StepError},
+    element::Element,
+    index::{SigCoord, SigIndex},
+};
+
+/// Which elements pair up, which are removed alone or in order, and when an element is free.
+///
+/// Elements are stored as masks, where each element has the bit of its
+/// [index](Element::to_index).
+///
+/// The solver is not tied to the rules of Opus Magnum, which are available as
+/// [`RuleSet::STANDARD`], but works with any rule set, e.g. for fan-made variants with different
+/// pairs or element counts. A board is solved under other rules by creating its
+/// [`GamePosition`](crate::GamePosition) with [`GamePosition::with_rules`](crate::GamePosition::with_rules).
+///
+/// Elements that have to be removed in order are removed in the order of the [`Element`]s
+/// themselves, i.e. lead before tin and all metals before gold under the standard rules. Such
+/// elements can only be paired with elements that are not ordered.
+///
+/// The [feasibility checks](crate::Pruning) are derived from the standard rules, so they are
+/// skipped for any other rule set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RuleSet {
+    /// The number of each element in a freshly dealt game, ordered like the elements themselves.
+    pub initial_counts: [u8; 14],
+    /// How many consecutive neighbors of an element have to be empty for it to be free.
+    ///
+    /// Positions outside of the playing field count as empty.
+    pub free_neighbors: u8,
+    /// For each element, the elements it can be removed together with.
+    pairs: [u16; 14],
+    /// Elements that are removed on their own.
+    alone: u16,
+    /// Elements that have to be removed in order.
+    ordered: u16,
+}
+
+impl RuleSet {
+    /// The rules of Sigmar's Garden in Opus Magnum.
+    pub const STANDARD: Self = {
+        use Element::*;
+        Self {
+            initial_counts: [4, 8, 8, 8, 8, 4, 4, 5, 1, 1, 1, 1, 1, 1],
+            free_neighbors: 3,
+            pairs: [
+                elements(&[Salt, Air, Fire, Water, Earth]),
+                elements(&[Salt, Air]),
+                elements(&[Salt, Fire]),
+                elements(&[Salt, Water]),
+                elements(&[Salt, Earth]),
+                elements(&[Mors]),
+                elements(&[Vitae]),
+                elements(&[Lead, Tin, Iron, Copper, Silver]),
+                elements(&[Quicksilver]),
+                elements(&[Quicksilver]),
+                elements(&[Quicksilver]),
+                elements(&[Quicksilver]),
+                elements(&[Quicksilver]),
+                elements(&[]),
+            ],
+            alone: elements(&[Gold]),
+            ordered: elements(&[Lead, Tin, Iron, Copper, Silver, Gold]),
+        }
+    };
+
+    /// Allows or forbids removing the two elements together, which may also be the same.
+    pub fn pair(mut self, element1: Element, element2: Element, allowed: bool) -> Self {
+        set_bit(&mut self.pairs[element1 as usize - 1], element2, allowed);
+        set_bit(&mut self.pairs[element2 as usize - 1], element1, allowed);
+        self
+    }
+
+    /// Allows or forbids removing the element on its own.
+    pub fn alone(mut self, element: Element, allowed: bool) -> Self {
+        set_bit(&mut self.alone, element, allowed);
+        self
+    }
+
+    /// Sets whether the element has to be removed in order with the other ordered elements.
+    pub fn ordered(mut self, element: Element, ordered: bool) -> Self {
+        set_bit(&mut self.ordered, element, ordered);
+        self
+    }
+
+    /// Whether the two elements can be removed together, ignoring the order of elements.
+    pub fn can_pair(&self, element1: Element, element2: Element) -> bool {
+        self.pairs[element1 as usize - 1] & bit(element2) != 0
+    }
+
+    /// Whether the element is removed on its own, like gold under the standard rules.
+    pub fn is_removed_alone(&self, element: Element) -> bool {
+        self.alone & bit(element) != 0
+    }
+
+    /// Whether the element has to be removed in order, i.e. only once no ordered element before it
+    /// is left.
+    pub fn is_ordered(&self, element: Element) -> bool {
+        self.ordered & bit(element) != 0
+    }
+
+    /// Whether the board contains exactly the elements of a freshly dealt game.
+    pub fn is_valid_initial_state(&self, board: Board) -> bool {
+        board.element_counts() == self.initial_counts
+    }
+
+    /// Whether the remaining elements could be left over after removing elements from a freshly
+    /// dealt game.
+    ///
+    /// Only the number of each element is checked, not whether the removed elements could have
+    /// actually been free.
+    pub fn is_consistent(&self, board: Board) -> bool {
+        let counts = board.element_counts();
+        if counts
+            .iter()
+            .zip(self.initial_counts)
+            .any(|(&count, max)| count > max)
+        {
+            return false;
+        }
+
+        // Once an ordered element is left, none of the later ones can have been removed.
+        let untouched = Element::ALL
+            .into_iter()
+            .filter(|&element| self.is_ordered(element))
+            .skip_while(|&element| counts[element as usize - 1] == 0)
+            .all(|element| {
+                counts[element as usize - 1] == self.initial_counts[element as usize - 1]
+            });
+
+        untouched && (*self != Self::STANDARD || is_consistent_with_standard_pairs(counts))
+    }
+
+    /// Whether the position has enough consecutive empty neighbors to be removed.
+    ///
+    /// This does not check whether the position itself is occupied.
+    pub fn is_free(&self, board: Board, index: impl Into<SigIndex>) -> bool {
+        let empty = SigCoord::from(index.into())
+            .adjacent_cw()
+            .map(|coord| coord.and_then(|coord| board.get(coord)).is_none());
+        let needed = usize::from(self.free_neighbors);
+        needed == 0
+            || (0..6).any(|direction| (0..needed).all(|offset| empty[(direction + offset) % 6]))
+    }
+
+    /// Returns the board after making the step, or why the step cannot be made.
+    ///
+    /// The rules are checked directly on the board, independently of the move generator behind
+    /// [`RuleSet::valid_steps`].
+    pub fn apply(&self, board: Board, step: Step) -> Result<Board, StepError> {
+        let Step([index1, index2]) = step;
+        let element1 = board.get(index1).ok_or(StepError::Empty(index1))?;
+        let element2 = board.get(index2).ok_or(StepError::Empty(index2))?;
+
+        let alone = index1 == index2;
+        if alone && !self.is_removed_alone(element1) || !alone && !self.can_pair(element1, element2)
+        {
+            return Err(StepError::Mismatch(element1, element2));
+        }
+
+        let next_ordered = Element::ALL.into_iter().find(|&element| {
+            self.is_ordered(element)
+                && SigIndex::all().any(|index| board.get(index) == Some(element))
+        });
+        if let Some(next_ordered) = next_ordered {
+            let out_of_order = [element1, element2]
+                .into_iter()
+                .any(|element| self.is_ordered(element) && element != next_ordered);
+            if out_of_order {
+                return Err(if alone {
+                    StepError::GoldNotLast
+                } else {
+                    StepError::WrongMetalOrder(next_ordered)
+                });
+            }
+        }
+
+        for index in [index1, index2] {
+            if !self.is_free(board, index) {
+                return Err(StepError::NotFree(index));
+            }
+        }
+
+        let mut board = board;
+        board.remove(step);
+        Ok(board)
+    }
+
+    /// Returns a list of all possible moves that can be made on the board.
+    ///
+    /// The moves are sorted by likelihood of not leading to a rollback, with the most likely last,
+    /// so that popping off the end of the list is more efficient.
+    pub fn valid_steps(&self, board: Board) -> Vec<Step> {
+        BitBoard::from(board).valid_steps(self)
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// The checks that only hold for the pairs of the standard rules.
+fn is_consistent_with_standard_pairs(counts: [u8; 14]) -> bool {
+    let count = |element| counts[element as usize - 1];
+
+    // Each metal but gold is removed together with a quicksilver.
+    let remaining_metals = Element::METALS
+        .into_iter()
+        .filter(|&metal| count(metal) != 0)
+        .count();
+    if count(Element::Quicksilver) as usize != remaining_metals {
+        return false;
+    }
+
+    if count(Element::Vitae) != count(Element::Mors) {
+        return false;
+    }
+
+    // There is an even number of each cardinal element, so one with an odd count must have been
+    // removed together with a salt, and the other removed salts paired up.
+    let initial_salt = RuleSet::STANDARD.initial_counts[Element::Salt as usize - 1];
+    let removed_salt = initial_salt - count(Element::Salt);
+    let odd_cardinals = Element::CARDINALS
+        .into_iter()
+        .filter(|&element| count(element) % 2 == 1)
+        .count() as u8;
+    odd_cardinals <= removed_salt && (removed_salt - odd_cardinals).is_multiple_of(2)
+}
+
+const fn bit(element: Element) -> u16 {
+    1 << element as u16
+}
+
+const fn elements(elements: &[Element]) -> u16 {
+    let mut mask = 0;
+    let mut i = 0;
+    while i < elements.len() {
+        mask |= bit(elements[i]);
+        i += 1;
+    }
+    mask
+}
+
+fn set_bit(mask: &mut u16, element: Element, value: bool) {
+    if value {
+        *mask |= bit(element);
+    } else {
+        *mask &= !bit(element);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::solver::{GamePosition, SolveResult};
+
+    use super::*;
+
+    #[test]
+    fn standard_pairs_are_symmetric() {
+        for element1 in Element::ALL {
+            for element2 in Element::ALL {
+                assert_eq!(
+                    RuleSet::STANDARD.can_pair(element1, element2),
+                    RuleSet::STANDARD.can_pair(element2, element1)
+                );
+            }
+        }
+        assert!(RuleSet::STANDARD.can_pair(Element::Salt, Element::Fire));
+        assert!(!RuleSet::STANDARD.can_pair(Element::Fire, Element::Water));
+        assert!(!RuleSet::STANDARD.can_pair(Element::Gold, Element::Gold));
+    }
+
+    #[test]
+    fn variant_pairs_and_counts() {
+        // Vitae pairs with itself, and there is no mors at all.
+        let mut rules = RuleSet::STANDARD.pair(Element::Vitae, Element::Vitae, true);
+        rules.initial_counts[Element::Mors as usize - 1] = 0;
+
+        let board = Board::with_elements(&[(0, 0, Element::Vitae), (2, 2, Element::Vitae)]);
+        assert_eq!(GamePosition::new(board), None);
+        let position = GamePosition::with_rules(board, rules).unwrap();
+        let SolveResult::Solution(steps) = position.solve(Duration::from_secs(1)) else {
+            panic!("two vitae should be solvable");
+        };
+        assert_eq!(steps.len(), 1);
+        assert!(rules.apply(board, steps[0]).unwrap().is_solved());
+    }
+
+    #[test]
+    fn variant_free_neighbors() {
+        // Each fire has five empty neighbors.
+        let board = Board::with_elements(&[(0, 0, Element::Fire), (0, 1, Element::Fire)]);
+        assert_eq!(RuleSet::STANDARD.valid_steps(board).len(), 1);
+
+        let strict = RuleSet {
+            free_neighbors: 6,
+            ..RuleSet::STANDARD
+        };
+        assert!(!strict.is_free(board, SigCoord::new(0, 0).unwrap()));
+        assert!(strict.valid_steps(board).is_empty());
+    }
+}
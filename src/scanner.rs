@@ -1,21 +1,51 @@
-use std::{collections::BTreeMap, io::Cursor};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    io::Cursor,
+    ops::{Range, RangeInclusive},
+};
 
 use image::{
     imageops::{self, FilterType},
     io::Reader,
-    GenericImageView, ImageFormat, RgbImage, SubImage,
+    GenericImageView, GrayImage, ImageBuffer, ImageFormat, Luma, Pixel, RgbImage,
 };
 
 use crate::{
     board::Board,
     element::Element,
     index::{SigCoord, SigIndex},
-    screen::coord_to_screen,
-    solver::InitialBoard,
+    screen::Geometry,
+    solver::{GamePosition, InitialBoard},
 };
 
-pub(crate) struct Scanner {
+/// The size of the reference images.
+const SCAN_SIZE: u32 = 20;
+
+/// The range of tile widths to look for when locating the board.
+const MIN_TILE_WIDTH: i32 = 44;
+const MAX_TILE_WIDTH: i32 = 132;
+
+/// The step between tile widths when looking for the gold, which only has to find its center.
+const GOLD_WIDTH_STEP: usize = 8;
+
+/// How often to alternate between refining the center and the tile width of a located board.
+const LOCATE_REFINEMENTS: usize = 2;
+
+/// The largest average difference of a pixel to the gold for the board to count as found.
+const MAX_GOLD_DIFF: u32 = 24;
+
+/// Reads the board from screenshots of the game by comparing each position to reference images.
+///
+/// The reference images are embedded at a single scale and work at any other one: the part of the
+/// screenshot around each position is compared to them at the smaller of both sizes, by scaling
+/// down whichever is larger.
+///
+/// Unless a fixed [geometry](Scanner::geometry) is given, the board is [located](Scanner::locate)
+/// in each screenshot first, and nothing is scanned if it cannot be found.
+pub struct Scanner {
     ref_images: BTreeMap<ElementImageKey, RgbImage>,
+    geometry: Option<Geometry>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -26,37 +56,196 @@ enum ElementImageKey {
 }
 
 impl Scanner {
-    pub(crate) fn new() -> Self {
+    /// Creates a scanner that locates the board in each screenshot.
+    pub fn new() -> Self {
         Self {
             ref_images: ElementImageKey::load_ref_images(),
+            geometry: None,
         }
     }
 
-    pub(crate) fn scan_image(&self, image: &RgbImage) -> Option<InitialBoard> {
-        let mut board = Board::new();
-        for index in SigIndex::all() {
-            board.set(index, self.scan_element(image, index));
+    /// Always scans at the given geometry instead of locating the board in each screenshot.
+    pub fn geometry(mut self, geometry: Geometry) -> Self {
+        self.geometry = Some(geometry);
+        self
+    }
+
+    /// The geometry used to scan the screenshot: the fixed one if given, otherwise the located
+    /// one, or [`None`] if the board cannot be found.
+    pub fn geometry_for(&self, image: &RgbImage) -> Option<Geometry> {
+        self.geometry.or_else(|| self.locate(image))
+    }
+
+    /// Finds the playing field in a screenshot.
+    ///
+    /// The center is found by looking for the gold, which stays in the center until the very last
+    /// step, at a range of sizes. The tile width is the one at which the positions around the edge
+    /// of the playing field best match any of the reference images. Returns [`None`] if there is
+    /// no gold or the whole playing field does not fit on the screenshot.
+    pub fn locate(&self, image: &RgbImage) -> Option<Geometry> {
+        let gray = imageops::grayscale(image);
+        let golds = [
+            ElementImageKey::Normal(Element::Gold),
+            ElementImageKey::Blocked(Element::Gold),
+        ]
+        .map(|key| imageops::grayscale(&self.ref_images[&key]));
+
+        let find_gold = |geometry: Geometry, window: Option<((i32, i32), u32)>| {
+            let size = geometry.scan_size;
+            let (margin_x, margin_y) = geometry.margin();
+            let (width, height) = gray.dimensions();
+            let (mut xs, mut ys) = (
+                margin_x..width.saturating_sub(margin_x),
+                margin_y..height.saturating_sub(margin_y),
+            );
+            if let Some(((x, y), radius)) = window {
+                xs = xs.start.max((x as u32).saturating_sub(radius))
+                    ..xs.end.min(x as u32 + radius + 1);
+                ys = ys.start.max((y as u32).saturating_sub(radius))
+                    ..ys.end.min(y as u32 + radius + 1);
+            }
+            golds
+                .iter()
+                .filter_map(|gold| {
+                    let template = imageops::resize(gold, size, size, FilterType::Triangle);
+                    find_template(&gray, &template, xs.clone(), ys.clone())
+                })
+                .min_by_key(|&(_, diff)| diff)
+        };
+
+        let (center, _) = (MIN_TILE_WIDTH..=MAX_TILE_WIDTH)
+            .step_by(GOLD_WIDTH_STEP)
+            .filter_map(|tile_width| find_gold(Geometry::with_tile_width((0, 0), tile_width), None))
+            .min_by_key(|&(_, diff)| diff)
+            .filter(|&(_, diff)| diff <= MAX_GOLD_DIFF)?;
+
+        let ref_images = self
+            .ref_images
+            .values()
+            .map(imageops::grayscale)
+            .collect::<Vec<_>>();
+        let edge = SigIndex::all()
+            .filter(|&index| SigCoord::from(index).adjacent_cw().contains(&None))
+            .collect::<Vec<_>>();
+        let fits = |geometry: &Geometry| geometry.fits(image.dimensions());
+        let edge_diff = |geometry: &Geometry| {
+            edge.iter()
+                .map(|&index| {
+                    let scan_image = scan_image(&gray, *geometry, index, SCAN_SIZE);
+                    ref_images
+                        .iter()
+                        .map(|ref_image| average_diff(&scan_image, ref_image))
+                        .min()
+                        .unwrap()
+                })
+                .sum::<u32>()
+        };
+
+        let best_width = |center, tile_widths: RangeInclusive<i32>| {
+            tile_widths
+                .map(|tile_width| Geometry::with_tile_width(center, tile_width))
+                .filter(fits)
+                .min_by_key(edge_diff)
+        };
+        let mut geometry = best_width(center, MIN_TILE_WIDTH..=MAX_TILE_WIDTH)?;
+
+        // The gold was found at a slightly different size, which may put the center off by a few
+        // pixels, which in turn may throw off the tile width a little.
+        for _ in 0..LOCATE_REFINEMENTS {
+            let window = (geometry.center, (geometry.tile_width as u32).div_ceil(16));
+            let (center, _) = find_gold(geometry, Some(window))?;
+            let tile_width = geometry.tile_width;
+            geometry = best_width(center, tile_width - 2..=tile_width + 2)?;
         }
-        InitialBoard::new(board)
+        Some(geometry)
     }
 
-    fn scan_element(&self, image: &RgbImage, index: SigIndex) -> Option<Element> {
-        let (x, y, width, height) = scan_position(index);
-        let scan_image = image.view(x, y, width, height);
+    /// Scans a screenshot, returning the board if it is a freshly dealt game.
+    pub fn scan_image(&self, image: &RgbImage) -> Option<InitialBoard> {
+        self.scan_board(image).and_then(InitialBoard::new)
+    }
+
+    /// Scans a screenshot, returning the board if it is a game in progress, including freshly
+    /// dealt ones.
+    ///
+    /// An empty board is not considered a game, since it also results from a screenshot that does
+    /// not show the game at all.
+    pub fn scan_game_position(&self, image: &RgbImage) -> Option<GamePosition> {
+        self.scan_game_position_at(image, self.geometry_for(image)?)
+    }
+
+    /// Like [`Scanner::scan_game_position`], but at the given geometry.
+    pub fn scan_game_position_at(
+        &self,
+        image: &RgbImage,
+        geometry: Geometry,
+    ) -> Option<GamePosition> {
+        let board = self.scan_board_at(image, geometry)?;
+        if board.is_solved() {
+            None
+        } else {
+            GamePosition::new(board)
+        }
+    }
+
+    /// Scans a screenshot, returning whatever board it contains.
+    ///
+    /// Returns [`None`] if the playing field cannot be located or does not fit on the screenshot,
+    /// rather than scanning something else as an empty board.
+    pub fn scan_board(&self, image: &RgbImage) -> Option<Board> {
+        self.scan_board_at(image, self.geometry_for(image)?)
+    }
 
-        // scan_image
-        //     .to_image()
-        //     .save(format!("scan/{x}_{y}.png"))
-        //     .unwrap();
+    /// Like [`Scanner::scan_board`], but at the given geometry.
+    pub fn scan_board_at(&self, image: &RgbImage, geometry: Geometry) -> Option<Board> {
+        if !geometry.fits(image.dimensions()) {
+            return None;
+        }
+        let size = geometry.scan_size.min(SCAN_SIZE);
+        let ref_images = self.ref_images_at(size);
+        let mut board = Board::new();
+        for index in SigIndex::all() {
+            let scan_image = scan_image(image, geometry, index, size);
+
+            // scan_image
+            //     .save(format!("scan/{}.png", SigCoord::from(index)))
+            //     .unwrap();
 
-        best_element(&self.score(scan_image))
+            board.set(index, best_element(&score(&scan_image, &ref_images)));
+        }
+        Some(board)
+    }
+
+    /// The reference images scaled down to the given size.
+    fn ref_images_at(&self, size: u32) -> Cow<'_, BTreeMap<ElementImageKey, RgbImage>> {
+        if size == SCAN_SIZE {
+            return Cow::Borrowed(&self.ref_images);
+        }
+        Cow::Owned(
+            self.ref_images
+                .iter()
+                .map(|(&key, ref_image)| {
+                    let ref_image = imageops::resize(ref_image, size, size, FilterType::Triangle);
+                    (key, ref_image)
+                })
+                .collect(),
+        )
     }
+}
 
-    fn score(&self, scan_image: SubImage<&RgbImage>) -> Box<[(ElementImageKey, u32)]> {
-        self.ref_images
-            .iter()
-            .map(|(element, ref_image)| (*element, compare_images(scan_image, ref_image)))
-            .collect()
+fn score(
+    scan_image: &RgbImage,
+    ref_images: &BTreeMap<ElementImageKey, RgbImage>,
+) -> Box<[(ElementImageKey, u32)]> {
+    ref_images
+        .iter()
+        .map(|(element, ref_image)| (*element, compare_images(scan_image, ref_image)))
+        .collect()
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -158,13 +347,14 @@ impl ElementImageKey {
     }
 }
 
-fn compare_images(image: SubImage<&RgbImage>, ref_image: &RgbImage) -> u32 {
+/// How different the image is from the reference image, which has to be of the same size.
+fn compare_images(image: &RgbImage, ref_image: &RgbImage) -> u32 {
     assert_eq!(image.dimensions(), ref_image.dimensions());
 
     let buffer = image
         .pixels()
         .zip(ref_image.pixels())
-        .flat_map(|((_, _, pixel), ref_pixel)| {
+        .flat_map(|(pixel, ref_pixel)| {
             [
                 pixel[0].abs_diff(ref_pixel[0]),
                 pixel[1].abs_diff(ref_pixel[1]),
@@ -190,9 +380,161 @@ const EDGE_FILTER: [f32; 9] = [
     -1.0, -1.0, -1.0,
 ];
 
-fn scan_position(coord: impl Into<SigCoord>) -> (u32, u32, u32, u32) {
-    const SCAN_SIZE: u32 = 20;
+/// The part of the screenshot around the position, scaled to the given size.
+fn scan_image<P: Pixel + 'static>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    geometry: Geometry,
+    coord: impl Into<SigCoord>,
+    size: u32,
+) -> ImageBuffer<P, Vec<P::Subpixel>> {
+    let scan_size = geometry.scan_size;
+    let (x, y) = geometry.coord_to_screen(coord);
+    let scan_image = image
+        .view(x - scan_size / 2, y - scan_size / 2, scan_size, scan_size)
+        .to_image();
+    if scan_size == size {
+        scan_image
+    } else {
+        imageops::resize(&scan_image, size, size, FilterType::Triangle)
+    }
+}
+
+/// The average difference per pixel between two grayscale images of the same size.
+fn average_diff(image: &GrayImage, other: &GrayImage) -> u32 {
+    average_diff_sampled(image, other, 1)
+}
+
+/// Like [`average_diff`], but only comparing every `step`-th pixel in both directions.
+fn average_diff_sampled(
+    image: &impl GenericImageView<Pixel = Luma<u8>>,
+    other: &GrayImage,
+    step: usize,
+) -> u32 {
+    let mut sum = 0;
+    let mut count = 0;
+    for y in (0..other.height()).step_by(step) {
+        for x in (0..other.width()).step_by(step) {
+            sum += u32::from(image.get_pixel(x, y)[0].abs_diff(other.get_pixel(x, y)[0]));
+            count += 1;
+        }
+    }
+    sum / count
+}
+
+/// Finds where the template best matches the image among the given centers, returning the center
+/// and the average difference per pixel.
+///
+/// The image is first searched coarsely, comparing only every other pixel at every quarter of the
+/// template size, and then in full around the best match.
+fn find_template(
+    image: &GrayImage,
+    template: &GrayImage,
+    xs: Range<u32>,
+    ys: Range<u32>,
+) -> Option<((i32, i32), u32)> {
+    let size = template.width();
+    let diff = |x: u32, y: u32, step: usize| {
+        let view = image.view(x - size / 2, y - size / 2, size, size);
+        average_diff_sampled(&*view, template, step)
+    };
+
+    let stride = (size as usize / 4).max(1);
+    let (coarse_x, coarse_y) = ys
+        .clone()
+        .step_by(stride)
+        .flat_map(|y| xs.clone().step_by(stride).map(move |x| (x, y)))
+        .min_by_key(|&(x, y)| diff(x, y, 2))?;
+
+    let stride = stride as u32;
+    let near = |coarse: u32, range: &Range<u32>| {
+        coarse.saturating_sub(stride).max(range.start)..(coarse + stride + 1).min(range.end)
+    };
+    near(coarse_y, &ys)
+        .flat_map(|y| near(coarse_x, &xs).map(move |x| (x, y)))
+        .map(|(x, y)| ((x as i32, y as i32), diff(x, y, 1)))
+        .min_by_key(|&(_, diff)| diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
 
-    let (x, y) = coord_to_screen(coord);
-    (x - SCAN_SIZE / 2, y - SCAN_SIZE / 2, SCAN_SIZE, SCAN_SIZE)
+    use crate::generator::Generator;
+
+    use super::*;
+
+    /// Draws the board by pasting the reference images, scaled to the geometry, onto a plain
+    /// background with the board in the center.
+    fn render(scanner: &Scanner, board: Board, geometry: Geometry) -> RgbImage {
+        let (width, height) = (2 * geometry.center.0 as u32, 2 * geometry.center.1 as u32);
+        let mut image = RgbImage::from_pixel(width, height, Rgb([40, 32, 28]));
+        let size = geometry.scan_size;
+        for index in SigIndex::all() {
+            // Only the gold in the center is blocked, like in a freshly dealt game.
+            let key = match board.get(index) {
+                None => ElementImageKey::Empty,
+                Some(Element::Gold) => ElementImageKey::Blocked(Element::Gold),
+                Some(element) => ElementImageKey::Normal(element),
+            };
+            let ref_image =
+                imageops::resize(&scanner.ref_images[&key], size, size, FilterType::Triangle);
+            let (x, y) = geometry.coord_to_screen(index);
+            imageops::replace(
+                &mut image,
+                &ref_image,
+                i64::from(x - size / 2),
+                i64::from(y - size / 2),
+            );
+        }
+        image
+    }
+
+    #[test]
+    fn locates_and_scans_boards() {
+        let scanner = Scanner::new();
+        let board = Generator::new(3).generate().board();
+        for geometry in [
+            Geometry::with_tile_width((650, 480), 66),
+            Geometry::with_tile_width((720, 500), 85),
+        ] {
+            let image = render(&scanner, board, geometry);
+            assert_eq!(scanner.locate(&image), Some(geometry));
+            assert_eq!(scanner.scan_board_at(&image, geometry), Some(board));
+        }
+
+        let blank = RgbImage::from_pixel(1400, 1000, Rgb([40, 32, 28]));
+        assert_eq!(scanner.locate(&blank), None);
+    }
+
+    #[test]
+    fn small_screenshots_are_rejected() {
+        let scanner = Scanner::new();
+        let image = RgbImage::new(800, 600);
+        assert_eq!(scanner.scan_board(&image), None);
+        assert_eq!(scanner.scan_board_at(&image, Geometry::DEFAULT), None);
+    }
+
+    #[test]
+    fn screenshots_without_the_board_are_rejected() {
+        let scanner = Scanner::new();
+        let image = RgbImage::from_pixel(2560, 1440, Rgb([255, 255, 255]));
+        assert_eq!(scanner.scan_board(&image), None);
+    }
+
+    #[test]
+    fn scans_at_any_scale() {
+        let scanner = Scanner::new();
+        let board = Generator::new(4).generate().board();
+        // From a small window to fullscreen at 4K.
+        for tile_width in [36, 45, 57, 99, 132] {
+            let center = (6 * tile_width, 6 * tile_width);
+            let geometry = Geometry::with_tile_width(center, tile_width);
+            let image = render(&scanner, board, geometry);
+            assert_eq!(
+                scanner.scan_board_at(&image, geometry),
+                Some(board),
+                "{geometry:?}"
+            );
+        }
+    }
 }
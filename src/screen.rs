@@ -1,16 +1,321 @@
+//! Where the game is on the screen.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
 use crate::index::SigCoord;
 
-pub(crate) fn coord_to_screen(coord: impl Into<SigCoord>) -> (u32, u32) {
-    const CENTER_X: i32 = 1216;
-    const CENTER_Y: i32 = 504;
-    const TILE_WIDTH: i32 = 66;
-    const TILE_HEIGHT: i32 = 57;
-
-    let coord = coord.into();
-    let row = coord.row() as i32;
-    let col = coord.col() as i32;
-    (
-        (CENTER_X + col * TILE_WIDTH - row * TILE_WIDTH / 2) as u32,
-        (CENTER_Y + row * TILE_HEIGHT) as u32,
-    )
+/// Where the button for the next game is relative to the center of the playing field, at the
+/// default geometry.
+const NEW_GAME_OFFSET: (i32, i32) = (870 - 1216, 886 - 504);
+
+/// Where the playing field is on the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Geometry {
+    /// The pixel position of the center of the playing field.
+    pub center: (i32, i32),
+    /// The horizontal distance between neighboring positions in the same row.
+    pub tile_width: i32,
+    /// The vertical distance between neighboring rows.
+    pub tile_height: i32,
+    /// The size of the square around each position that is compared to the reference images.
+    pub scan_size: u32,
+}
+
+impl Geometry {
+    /// The fixed geometry the solver used before it located the board, which the aspect ratio
+    /// and scale of other geometries are derived from.
+    pub const DEFAULT: Self = Self {
+        center: (1216, 504),
+        tile_width: 66,
+        tile_height: 57,
+        scan_size: 20,
+    };
+
+    /// Creates the geometry of a playing field scaled to the given tile width, keeping the aspect
+    /// ratio of the hexagonal grid and scaling the scan size along with it.
+    pub fn with_tile_width(center: (i32, i32), tile_width: i32) -> Self {
+        let tile_height = (tile_width * Self::DEFAULT.tile_height + Self::DEFAULT.tile_width / 2)
+            / Self::DEFAULT.tile_width;
+        let scale = f64::from(tile_width) / f64::from(Self::DEFAULT.tile_width);
+        Self {
+            center,
+            tile_width,
+            tile_height,
+            scan_size: (f64::from(Self::DEFAULT.scan_size) * scale).round() as u32,
+        }
+    }
+
+    /// How far the center of the playing field has to be from the borders of an image for all
+    /// positions to be scanned.
+    pub fn margin(self) -> (u32, u32) {
+        let half_size = self.scan_size / 2 + 1;
+        (
+            5 * self.tile_width.unsigned_abs() + half_size,
+            5 * self.tile_height.unsigned_abs() + half_size,
+        )
+    }
+
+    /// Whether all positions and the squares scanned around them lie within an image of the
+    /// given size.
+    pub fn fits(self, (width, height): (u32, u32)) -> bool {
+        let (margin_x, margin_y) = self.margin();
+        let fits_between = |center: i32, margin: u32, size: u32| {
+            i64::from(center) >= i64::from(margin)
+                && i64::from(center) + i64::from(margin) < i64::from(size)
+        };
+        fits_between(self.center.0, margin_x, width)
+            && fits_between(self.center.1, margin_y, height)
+    }
+
+    /// How much larger the playing field is than at the [default geometry](Self::DEFAULT).
+    pub fn scale(self) -> f64 {
+        f64::from(self.tile_width) / f64::from(Self::DEFAULT.tile_width)
+    }
+
+    /// The pixel position of the center of the given position on the screen, which only lies on a
+    /// screen the geometry [fits](Self::fits).
+    pub fn coord_to_screen(self, coord: impl Into<SigCoord>) -> (u32, u32) {
+        let coord = coord.into();
+        let row = coord.row() as i32;
+        let col = coord.col() as i32;
+        (
+            (self.center.0 + col * self.tile_width - row * self.tile_width / 2) as u32,
+            (self.center.1 + row * self.tile_height) as u32,
+        )
+    }
+
+    /// The pixel position at the given offset from the center, where the offset is measured at the
+    /// [default geometry](Self::DEFAULT) and scaled along with the playing field.
+    pub fn offset_to_screen(self, (x, y): (i32, i32)) -> (u32, u32) {
+        let scale = self.scale();
+        (
+            (f64::from(self.center.0) + f64::from(x) * scale).round() as u32,
+            (f64::from(self.center.1) + f64::from(y) * scale).round() as u32,
+        )
+    }
+}
+
+impl Default for Geometry {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Where the game is on the screen.
+///
+/// It can be loaded from a TOML file like the bundled [presets](Layout::PRESETS):
+///
+/// ```toml
+/// # The index of the monitor, in the order the system lists them.
+/// monitor = 0
+/// # The "New Game" button; placed relative to the playing field if omitted.
+/// new_game = [870, 886]
+///
+/// # The playing field; located in each screenshot if omitted.
+/// [board]
+/// center = [1216, 504]
+/// tile_width = 66
+/// tile_height = 57
+/// scan_size = 20
+/// ```
+///
+/// All positions are in pixels relative to the top left corner of the monitor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Layout {
+    /// The index of the monitor the game is on, in the order the system lists them.
+    #[serde(default)]
+    pub monitor: usize,
+    /// Where the playing field is, or [`None`] to [locate](crate::Scanner::locate) it in each
+    /// screenshot.
+    #[serde(default)]
+    pub board: Option<Geometry>,
+    /// The pixel position of the "New Game" button, or [`None`] to place it relative to the
+    /// playing field.
+    #[serde(default)]
+    pub new_game: Option<(u32, u32)>,
+}
+
+impl Layout {
+    /// The bundled layouts by name.
+    ///
+    /// `auto` locates the board in each screenshot on the first monitor, and `fixed` uses the
+    /// [default geometry](Geometry::DEFAULT) on it.
+    pub const PRESETS: [(&'static str, &'static str); 2] = [
+        ("auto", include_str!("../layouts/auto.toml")),
+        ("fixed", include_str!("../layouts/fixed.toml")),
+    ];
+
+    /// Returns the bundled layout with the given name, if any.
+    pub fn preset(name: &str) -> Option<Self> {
+        Self::PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .map(|(_, text)| Self::from_toml(text).expect("bundled layouts are valid"))
+    }
+
+    /// Reads a layout from a TOML file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LayoutError> {
+        Self::from_toml(&fs::read_to_string(path).map_err(LayoutError::Io)?)
+    }
+
+    /// Parses a layout in TOML.
+    ///
+    /// The playing field has to have positive sizes and may not reach past the top or left edge
+    /// of the monitor; whether it reaches past the others is only known with the [size of the
+    /// monitor](Self::check).
+    pub fn from_toml(text: &str) -> Result<Self, LayoutError> {
+        let layout: Self = toml::from_str(text).map_err(LayoutError::Parse)?;
+        if let Some(geometry) = layout.board {
+            if geometry.tile_width <= 0
+                || geometry.tile_height <= 0
+                || geometry.scan_size == 0
+                || !geometry.fits((u32::MAX, u32::MAX))
+            {
+                return Err(LayoutError::InvalidBoard);
+            }
+        }
+        Ok(layout)
+    }
+
+    /// Checks that the playing field and the "New Game" button lie on a monitor of the given size.
+    pub fn check(&self, (width, height): (u32, u32)) -> Result<(), LayoutError> {
+        let board_fits = self
+            .board
+            .is_none_or(|geometry| geometry.fits((width, height)));
+        let button_fits = self.new_game.is_none_or(|(x, y)| x < width && y < height);
+        if board_fits && button_fits {
+            Ok(())
+        } else {
+            Err(LayoutError::OutOfBounds(width, height))
+        }
+    }
+
+    /// The pixel position of the "New Game" button, given where the playing field was found.
+    pub fn new_game_button(&self, geometry: Geometry) -> (u32, u32) {
+        self.new_game
+            .unwrap_or_else(|| geometry.offset_to_screen(NEW_GAME_OFFSET))
+    }
+}
+
+/// Why a [`Layout`] could not be loaded.
+#[derive(Debug)]
+pub enum LayoutError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The file is not a valid layout.
+    Parse(toml::de::Error),
+    /// The playing field has a size that is not positive or reaches past the top or left edge of
+    /// the monitor.
+    InvalidBoard,
+    /// The width and height of the monitor, which the layout does not fit on.
+    OutOfBounds(u32, u32),
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "could not read layout: {error}"),
+            Self::Parse(error) => write!(f, "invalid layout: {error}"),
+            Self::InvalidBoard => write!(
+                f,
+                "invalid layout: the board must have a positive size and lie on the screen"
+            ),
+            Self::OutOfBounds(width, height) => {
+                write!(f, "layout does not fit on a {width}x{height} monitor")
+            }
+        }
+    }
+}
+
+impl Error for LayoutError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Parse(error) => Some(error),
+            Self::InvalidBoard | Self::OutOfBounds(..) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_are_valid() {
+        for (name, _) in Layout::PRESETS {
+            let layout = Layout::preset(name).unwrap();
+            assert_eq!(layout.monitor, 0);
+        }
+        assert_eq!(Layout::preset("auto"), Some(Layout::default()));
+        assert_eq!(
+            Layout::preset("fixed").unwrap().board,
+            Some(Geometry::DEFAULT)
+        );
+        assert_eq!(Layout::preset("640x480"), None);
+    }
+
+    #[test]
+    fn new_game_button_follows_the_board() {
+        let layout = Layout::default();
+        assert_eq!(layout.new_game_button(Geometry::DEFAULT), (870, 886));
+        let fixed = Layout {
+            new_game: Some((10, 20)),
+            ..layout
+        };
+        assert_eq!(fixed.new_game_button(Geometry::DEFAULT), (10, 20));
+    }
+
+    #[test]
+    fn invalid_layouts_are_rejected() {
+        assert!(matches!(
+            Layout::from_toml("monitor = \"first\""),
+            Err(LayoutError::Parse(_))
+        ));
+        assert!(matches!(
+            Layout::from_toml("[board]\ncenter = [1, 2]"),
+            Err(LayoutError::Parse(_))
+        ));
+        assert!(matches!(
+            Layout::load("no-such-layout.toml"),
+            Err(LayoutError::Io(_))
+        ));
+        for board in [
+            "center = [1216, 504]\ntile_width = 0\ntile_height = 57\nscan_size = 20",
+            "center = [1216, 504]\ntile_width = 66\ntile_height = 57\nscan_size = 0",
+            "center = [100, 504]\ntile_width = 66\ntile_height = 57\nscan_size = 20",
+            "center = [1216, 504]\ntile_width = 66\ntile_height = -57\nscan_size = 20",
+        ] {
+            assert!(matches!(
+                Layout::from_toml(&format!("[board]\n{board}")),
+                Err(LayoutError::InvalidBoard)
+            ));
+        }
+    }
+
+    #[test]
+    fn layouts_are_checked_against_the_monitor() {
+        for (name, _) in Layout::PRESETS {
+            assert!(Layout::preset(name).unwrap().check((1920, 1080)).is_ok());
+        }
+        let layout = Layout::preset("fixed").unwrap();
+        assert!(matches!(
+            layout.check((1280, 720)),
+            Err(LayoutError::OutOfBounds(1280, 720))
+        ));
+        let button_outside = Layout {
+            new_game: Some((2560, 100)),
+            ..layout
+        };
+        assert!(button_outside.check((2560, 1440)).is_err());
+    }
 }
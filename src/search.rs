@@ -0,0 +1,509 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicU8, Ordering},
+        Arc, Condvar, Mutex, OnceLock,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{
+    bitboard::BitBoard,
+    board::Step,
+    pruning::Pruning,
+    rules::RuleSet,
+    solver::{GamePosition, SolveStats},
+};
+
+/// How much memory the solver may use to remember positions that do not lead to a solution.
+pub(crate) const DEAD_POSITIONS_MEMORY: usize = 32 << 20;
+
+/// How many positions the table of dead positions allocates at once, which takes 64 KiB.
+const DEAD_POSITIONS_CHUNK_LEN: usize = 1 << 13;
+
+/// How often a [`SearchHandle`] updates its statistics.
+const HANDLE_STATS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A depth-first search for a solution that can be run in portions.
+///
+/// Unlike [`GamePosition::solve`], running out of budget does not throw away the search state, so
+/// the search can be continued later on by simply calling [`Search::run`] again.
+pub struct Search {
+    initial_board: BitBoard,
+    board: BitBoard,
+    valid_steps: Vec<Vec<Step>>,
+    final_steps: Vec<Step>,
+    /// The number of steps that were applied before starting the search, which are never undone.
+    root_depth: usize,
+    dead_positions: Arc<DeadPositions>,
+    rules: RuleSet,
+    pruning: Pruning,
+    /// Breaks ties between equally promising steps when set.
+    rng: Option<StdRng>,
+    stats: SolveStats,
+    control: SearchControl,
+    finished: Option<SearchStatus>,
+}
+
+/// How much work [`Search::run`] may do before pausing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Budget {
+    /// Runs until the search is finished, paused or cancelled.
+    Unlimited,
+    /// Runs for at most this many iterations, each of which applies or undoes one step.
+    Iterations(u64),
+    /// Runs for at most this long.
+    Time(Duration),
+}
+
+/// The state of a [`Search`] after running it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SearchStatus {
+    /// The steps that clear the board, in order.
+    Solved(Vec<Step>),
+    /// Every possible sequence of steps was tried without clearing the board.
+    Unsolvable,
+    /// The budget ran out or the search was paused, but it can be resumed.
+    Paused,
+    /// The search was cancelled and cannot be resumed.
+    Cancelled,
+}
+
+impl Search {
+    /// Creates a search for a solution of the position, which starts once it is run.
+    pub fn new(position: impl Into<GamePosition>) -> Self {
+        let position = position.into();
+        Self::below(
+            BitBoard::from(position.board()),
+            position.rules(),
+            Vec::new(),
+            Arc::new(DeadPositions::with_memory(DEAD_POSITIONS_MEMORY)),
+            SearchControl::default(),
+        )
+    }
+
+    /// Only searches the positions reachable after applying `steps`, e.g. to split up the search
+    /// between several threads.
+    pub(crate) fn below(
+        initial_board: BitBoard,
+        rules: RuleSet,
+        steps: Vec<Step>,
+        dead_positions: Arc<DeadPositions>,
+        control: SearchControl,
+    ) -> Self {
+        let mut board = initial_board;
+        for step in &steps {
+            board.remove(step.0[0]);
+            board.remove(step.0[1]);
+        }
+        let finished = board
+            .is_solved()
+            .then(|| SearchStatus::Solved(steps.clone()));
+        let valid_steps = board.valid_steps(&rules);
+        let mut stats = SolveStats::default();
+        stats.expanded(steps.len(), valid_steps.len());
+        Self {
+            initial_board,
+            board,
+            valid_steps: vec![valid_steps],
+            root_depth: steps.len(),
+            final_steps: steps,
+            dead_positions,
+            rules,
+            pruning: Pruning::default(),
+            rng: None,
+            stats,
+            control,
+            finished,
+        }
+    }
+
+    /// Which feasibility checks to apply after each step, which defaults to all of them.
+    pub fn pruning(mut self, pruning: Pruning) -> Self {
+        self.pruning = pruning;
+        self
+    }
+
+    /// Tries steps that remove the same elements in a random order, which is otherwise fixed.
+    ///
+    /// The order between different kinds of steps stays the same, e.g. pairs of salt are still
+    /// tried last. The same seed always results in the same order.
+    pub fn randomize(mut self, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        if let Some(steps) = self.valid_steps.last_mut() {
+            shuffle_ties(steps, &self.board, &mut rng);
+        }
+        self.rng = Some(rng);
+        self
+    }
+
+    /// Statistics about the search so far.
+    pub fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    /// Allows pausing or cancelling the search from other threads.
+    pub fn control(&self) -> SearchControl {
+        self.control.clone()
+    }
+
+    /// Continues the search until it is finished, the budget runs out, or it is paused or
+    /// cancelled through its [`SearchControl`].
+    ///
+    /// Once the search is finished, the same result is returned on every call.
+    pub fn run(&mut self, budget: Budget) -> SearchStatus {
+        if let Some(status) = &self.finished {
+            return status.clone();
+        }
+
+        let start = Instant::now();
+        let elapsed_before = self.stats.elapsed;
+        let mut iterations = 0;
+
+        let status = loop {
+            match self.control.state() {
+                ControlState::Running => {}
+                ControlState::Paused => break SearchStatus::Paused,
+                ControlState::Cancelled => break SearchStatus::Cancelled,
+            }
+
+            let budget_exhausted = match budget {
+                Budget::Unlimited => false,
+                Budget::Iterations(max_iterations) => iterations >= max_iterations,
+                Budget::Time(duration) => start.elapsed() >= duration,
+            };
+            if budget_exhausted {
+                break SearchStatus::Paused;
+            }
+
+            if let Some(status) = self.iterate() {
+                break status;
+            }
+            iterations += 1;
+        };
+
+        self.stats.elapsed = elapsed_before + start.elapsed();
+        if matches!(
+            status,
+            SearchStatus::Solved(_) | SearchStatus::Unsolvable | SearchStatus::Cancelled
+        ) {
+            self.finished = Some(status.clone());
+        }
+        status
+    }
+
+    /// Runs the search on a separate thread, so that it can keep going while doing other work.
+    pub fn spawn(mut self) -> SearchHandle {
+        let control = self.control();
+        let stats = Arc::new(Mutex::new(self.stats.clone()));
+        let thread = {
+            let stats = stats.clone();
+            thread::spawn(move || loop {
+                let status = self.run(Budget::Time(HANDLE_STATS_INTERVAL));
+                *stats.lock().unwrap() = self.stats.clone();
+                match status {
+                    SearchStatus::Paused => self.control.wait_while_paused(),
+                    status => break status,
+                }
+            })
+        };
+        SearchHandle {
+            control,
+            stats,
+            thread,
+        }
+    }
+
+    /// Applies or undoes a single step, returning the result once the search is finished.
+    fn iterate(&mut self) -> Option<SearchStatus> {
+        let Some(step) = self.valid_steps.last_mut().unwrap().pop() else {
+            // Every step was tried, so there is no point in ever exploring this position again.
+            self.dead_positions.insert(self.board.occupied_hash());
+            self.valid_steps.pop();
+
+            if self.final_steps.len() == self.root_depth {
+                return Some(SearchStatus::Unsolvable);
+            }
+            let step = self.final_steps.pop().unwrap();
+            self.stats.backtracks += 1;
+            self.board.restore(step.0[0], &self.initial_board);
+            self.board.restore(step.0[1], &self.initial_board);
+            return None;
+        };
+
+        self.final_steps.push(step);
+        self.stats.max_depth = self.stats.max_depth.max(self.final_steps.len());
+        self.board.remove(step.0[0]);
+        self.board.remove(step.0[1]);
+
+        if self.board.is_solved() {
+            return Some(SearchStatus::Solved(self.final_steps.clone()));
+        }
+
+        let steps = if self.dead_positions.contains(self.board.occupied_hash()) {
+            Vec::new()
+        } else if !self.pruning.is_feasible(&self.board, &self.rules) {
+            self.stats.pruned += 1;
+            Vec::new()
+        } else {
+            let mut steps = self.board.valid_steps(&self.rules);
+            if let Some(rng) = &mut self.rng {
+                shuffle_ties(&mut steps, &self.board, rng);
+            }
+            self.stats.expanded(self.final_steps.len(), steps.len());
+            steps
+        };
+        self.valid_steps.push(steps);
+
+        None
+    }
+}
+
+/// Shuffles each run of consecutive steps that remove the same elements.
+fn shuffle_ties(steps: &mut [Step], board: &BitBoard, rng: &mut StdRng) {
+    let elements = |step: &Step| step.0.map(|index| board.get(index));
+    for ties in steps.chunk_by_mut(|step1, step2| elements(step1) == elements(step2)) {
+        ties.shuffle(rng);
+    }
+}
+
+/// Pauses, resumes or cancels a [`Search`], possibly from a different thread.
+#[derive(Clone, Debug, Default)]
+pub struct SearchControl(Arc<ControlShared>);
+
+#[derive(Debug, Default)]
+struct ControlShared {
+    state: AtomicU8,
+    lock: Mutex<()>,
+    resumed: Condvar,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ControlState {
+    Running,
+    Paused,
+    Cancelled,
+}
+
+impl SearchControl {
+    /// Makes the search return [`SearchStatus::Paused`] as soon as possible.
+    pub fn pause(&self) {
+        self.set_state(ControlState::Paused);
+    }
+
+    /// Allows a paused search to continue.
+    ///
+    /// Searches running through a [`SearchHandle`] continue on their own, while others have to be
+    /// [run](Search::run) again.
+    pub fn resume(&self) {
+        self.set_state(ControlState::Running);
+    }
+
+    /// Stops the search for good, making it return [`SearchStatus::Cancelled`].
+    pub fn cancel(&self) {
+        self.set_state(ControlState::Cancelled);
+    }
+
+    /// Whether the search was paused and not resumed since.
+    pub fn is_paused(&self) -> bool {
+        self.state() == ControlState::Paused
+    }
+
+    /// Whether the search was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.state() == ControlState::Cancelled
+    }
+
+    fn state(&self) -> ControlState {
+        match self.0.state.load(Ordering::Relaxed) {
+            0 => ControlState::Running,
+            1 => ControlState::Paused,
+            _ => ControlState::Cancelled,
+        }
+    }
+
+    fn set_state(&self, state: ControlState) {
+        let _lock = self.0.lock.lock().unwrap();
+        // Cancelling is final.
+        if self.state() != ControlState::Cancelled {
+            self.0.state.store(state as u8, Ordering::Relaxed);
+        }
+        self.0.resumed.notify_all();
+    }
+
+    fn wait_while_paused(&self) {
+        let lock = self.0.lock.lock().unwrap();
+        let _lock = self
+            .0
+            .resumed
+            .wait_while(lock, |_| self.state() == ControlState::Paused)
+            .unwrap();
+    }
+}
+
+/// A [`Search`] running on a separate thread, created with [`Search::spawn`].
+pub struct SearchHandle {
+    control: SearchControl,
+    stats: Arc<Mutex<SolveStats>>,
+    thread: JoinHandle<SearchStatus>,
+}
+
+impl SearchHandle {
+    /// The control to pause, resume or cancel the search with.
+    pub fn control(&self) -> &SearchControl {
+        &self.control
+    }
+
+    /// A recent snapshot of the statistics of the search.
+    pub fn stats(&self) -> SolveStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Whether the search is finished or cancelled, so that [`SearchHandle::join`] won't block.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the search to finish, returning its result and final statistics.
+    ///
+    /// This blocks forever if the search is paused and never resumed or cancelled.
+    pub fn join(self) -> (SearchStatus, SolveStats) {
+        let status = self.thread.join().unwrap();
+        let stats = self.stats.lock().unwrap().clone();
+        (status, stats)
+    }
+}
+
+/// A lossy set of positions that are known to not lead to a solution.
+///
+/// Positions are stored by their hash in a fixed-size table, overwriting older entries on
+/// collision, so memory usage stays bounded no matter how long the search runs.
+///
+/// The table can be shared between threads searching the same board. Since every entry is a single
+/// atomic, concurrent updates at worst lose an entry, which only costs some repeated work.
+///
+/// The table is allocated in chunks as positions are inserted into them, so searches that hit few
+/// dead ends, like most of those on nearly cleared boards, only pay for a small part of it.
+pub(crate) struct DeadPositions {
+    chunks: Box<[OnceLock<Box<[AtomicU64]>>]>,
+    chunk_len: usize,
+    shift: u32,
+}
+
+impl DeadPositions {
+    pub(crate) fn with_memory(bytes: usize) -> Self {
+        let len = (bytes / std::mem::size_of::<u64>())
+            .max(1)
+            .next_power_of_two();
+        let chunk_len = len.min(DEAD_POSITIONS_CHUNK_LEN);
+        Self {
+            chunks: (0..len / chunk_len).map(|_| OnceLock::new()).collect(),
+            chunk_len,
+            shift: u64::BITS - len.trailing_zeros(),
+        }
+    }
+
+    pub(crate) fn contains(&self, hash: u64) -> bool {
+        let (chunk, slot) = self.slot(hash);
+        self.chunks[chunk]
+            .get()
+            .is_some_and(|hashes| hashes[slot].load(Ordering::Relaxed) == Self::non_zero(hash))
+    }
+
+    pub(crate) fn insert(&self, hash: u64) {
+        let (chunk, slot) = self.slot(hash);
+        let hashes = self.chunks[chunk]
+            .get_or_init(|| (0..self.chunk_len).map(|_| AtomicU64::new(0)).collect());
+        hashes[slot].store(Self::non_zero(hash), Ordering::Relaxed);
+    }
+
+    /// The chunk and the slot within it, using the upper bits of the hash, since those are the
+    /// best mixed.
+    fn slot(&self, hash: u64) -> (usize, usize) {
+        let slot = hash.checked_shr(self.shift).unwrap_or(0) as usize;
+        (slot / self.chunk_len, slot % self.chunk_len)
+    }
+
+    /// Zero marks an empty slot, so it must not be used as a hash.
+    fn non_zero(hash: u64) -> u64 {
+        hash.max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::Generator;
+
+    use super::*;
+
+    #[test]
+    fn resuming_finds_the_same_solution() {
+        let mut generator = Generator::new(1).solvable(true);
+        for _ in 0..10 {
+            let board = generator.generate();
+
+            let expected = Search::new(board).run(Budget::Unlimited);
+            assert!(matches!(expected, SearchStatus::Solved(_)));
+
+            let mut search = Search::new(board);
+            let status = loop {
+                match search.run(Budget::Iterations(3)) {
+                    SearchStatus::Paused => continue,
+                    status => break status,
+                }
+            };
+            assert_eq!(status, expected);
+        }
+    }
+
+    #[test]
+    fn pause_and_cancel() {
+        let board = Generator::new(2).solvable(true).generate();
+        let mut search = Search::new(board);
+
+        search.control().pause();
+        assert_eq!(search.run(Budget::Unlimited), SearchStatus::Paused);
+        search.control().cancel();
+        search.control().resume();
+        assert_eq!(search.run(Budget::Unlimited), SearchStatus::Cancelled);
+    }
+
+    #[test]
+    fn spawned_search_can_be_cancelled() {
+        // The search does not finish on this board within a minute.
+        let handle = Search::new(Generator::new(10).generate()).spawn();
+        thread::sleep(Duration::from_millis(50));
+        assert!(!handle.is_finished());
+        handle.control().cancel();
+        let (status, stats) = handle.join();
+        assert_eq!(status, SearchStatus::Cancelled);
+        assert!(stats.nodes > 1);
+    }
+
+    #[test]
+    fn dead_positions_are_allocated_as_needed() {
+        let dead_positions = DeadPositions::with_memory(DEAD_POSITIONS_MEMORY);
+        let allocated = || {
+            dead_positions
+                .chunks
+                .iter()
+                .filter(|chunk| chunk.get().is_some())
+                .count()
+        };
+        assert!(!dead_positions.contains(42));
+        assert_eq!(allocated(), 0);
+        dead_positions.insert(42);
+        assert!(dead_positions.contains(42));
+        assert_eq!(allocated(), 1);
+        dead_positions.insert(u64::MAX);
+        assert!(dead_positions.contains(u64::MAX));
+        assert_eq!(allocated(), 2);
+
+        let small = DeadPositions::with_memory(1 << 10);
+        small.insert(42);
+        assert!(small.contains(42));
+        assert_eq!(small.chunks.len(), 1);
+    }
+}
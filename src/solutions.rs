@@ -0,0 +1,225 @@
+//! Enumerating all distinct solutions of a position.
+//!
+//! Many solutions only differ in the order in which the same pairs are removed, e.g. because two
+//! steps far apart from each other can be made in either order. Such solutions are counted once,
+//! so two solutions are distinct if they remove a different set of pairs.
+//!
+//! To avoid exploring every order of the same pairs, each set of pairs removed so far is only
+//! explored once, no matter in which order it was reached. Only positions that are known to be
+//! solvable are explored, so most of the work goes into actually finding solutions.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    bitboard::BitBoard,
+    board::Step,
+    rules::RuleSet,
+    search::{Budget, DeadPositions, Search, SearchControl, SearchStatus, DEAD_POSITIONS_MEMORY},
+    solver::GamePosition,
+};
+
+/// The result of [`GamePosition::solutions`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solutions {
+    /// The distinct solutions, in the order they were found.
+    pub solutions: Vec<Vec<Step>>,
+    /// Whether all solutions were found before reaching the limit or the timeout.
+    pub complete: bool,
+}
+
+pub(crate) fn enumerate(position: GamePosition, limit: usize, timeout: Duration) -> Solutions {
+    let initial_board = BitBoard::from(position.board());
+    let mut enumeration = Enumeration {
+        initial_board,
+        rules: position.rules(),
+        limit,
+        deadline: Instant::now() + timeout,
+        timed_out: false,
+        dead_positions: Arc::new(DeadPositions::with_memory(DEAD_POSITIONS_MEMORY)),
+        solvable_positions: HashSet::new(),
+        explored: HashSet::new(),
+        solutions: Vec::new(),
+        steps: Vec::new(),
+    };
+    let complete = limit > 0 && enumeration.visit(initial_board);
+    Solutions {
+        solutions: enumeration.solutions,
+        complete,
+    }
+}
+
+struct Enumeration {
+    initial_board: BitBoard,
+    rules: RuleSet,
+    limit: usize,
+    deadline: Instant,
+    /// Whether the deadline passed before all positions were explored.
+    timed_out: bool,
+    dead_positions: Arc<DeadPositions>,
+    /// Hashes of positions that are known to have at least one solution.
+    solvable_positions: HashSet<u64>,
+    /// Hashes of the sets of pairs that were already removed on the way to an explored position.
+    explored: HashSet<u64>,
+    solutions: Vec<Vec<Step>>,
+    steps: Vec<Step>,
+}
+
+impl Enumeration {
+    /// Explores all positions reachable from `board`, returning `false` once the limit or the
+    /// deadline is reached.
+    fn visit(&mut self, board: BitBoard) -> bool {
+        if Instant::now() >= self.deadline {
+            self.timed_out = true;
+            return false;
+        }
+
+        // Every solution found from here on would already have been found from the same pairs.
+        if !self.explored.insert(self.pairs_hash()) {
+            return true;
+        }
+
+        if board.is_solved() {
+            self.solutions.push(self.steps.clone());
+            return self.solutions.len() < self.limit;
+        }
+
+        if !self.is_solvable(board) {
+            return !self.timed_out;
+        }
+
+        for step in board.valid_steps(&self.rules).into_iter().rev() {
+            let mut next_board = board;
+            next_board.remove(step.0[0]);
+            next_board.remove(step.0[1]);
+            self.steps.push(step);
+            let keep_going = self.visit(next_board);
+            self.steps.pop();
+            if !keep_going {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Whether there is any solution at all, using a regular search that gives up at the deadline.
+    fn is_solvable(&mut self, board: BitBoard) -> bool {
+        let hash = board.occupied_hash();
+        if self.solvable_positions.contains(&hash) {
+            return true;
+        }
+        if self.dead_positions.contains(hash) {
+            return false;
+        }
+
+        let mut search = Search::below(
+            self.initial_board,
+            self.rules,
+            self.steps.clone(),
+            self.dead_positions.clone(),
+            SearchControl::default(),
+        );
+        let budget = Budget::Time(self.deadline.saturating_duration_since(Instant::now()));
+        let solution = match search.run(budget) {
+            SearchStatus::Solved(solution) => solution,
+            SearchStatus::Paused => {
+                self.timed_out = true;
+                return false;
+            }
+            _ => return false,
+        };
+
+        // Every position on the way to the solution is solvable as well.
+        let mut board = board;
+        self.solvable_positions.insert(hash);
+        for step in &solution[self.steps.len()..] {
+            board.remove(step.0[0]);
+            board.remove(step.0[1]);
+            self.solvable_positions.insert(board.occupied_hash());
+        }
+        true
+    }
+
+    /// A hash of the pairs removed so far, independent of their order.
+    fn pairs_hash(&self) -> u64 {
+        let mut pairs = self
+            .steps
+            .iter()
+            .map(|&Step(mut pair)| {
+                pair.sort_unstable();
+                pair
+            })
+            .collect::<Vec<_>>();
+        pairs.sort_unstable();
+        let mut hasher = DefaultHasher::new();
+        pairs.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::Board, element::Element, generator::Generator};
+
+    use super::*;
+
+    #[test]
+    fn independent_steps_count_once() {
+        // Two pairs of fire far apart from each other can be removed in either order, and also
+        // paired up crosswise.
+        let board = Board::with_elements(&[
+            (-5, -5, Element::Fire),
+            (-5, -4, Element::Fire),
+            (5, 5, Element::Fire),
+            (5, 4, Element::Fire),
+            (0, 0, Element::Gold),
+        ]);
+        let solutions = GamePosition::new(board)
+            .unwrap()
+            .solutions(100, Duration::from_secs(60));
+        assert!(solutions.complete);
+        // Three ways to pair up four fires, each with the gold removed in any order.
+        assert_eq!(solutions.solutions.len(), 3);
+    }
+
+    #[test]
+    fn solutions_are_distinct() {
+        let board = Generator::new(6).solvable(true).generate();
+        let solutions = GamePosition::from(board).solutions(20, Duration::from_secs(60));
+        assert_eq!(solutions.solutions.len(), 20);
+        assert!(!solutions.complete);
+
+        let sets = solutions
+            .solutions
+            .iter()
+            .map(|steps| {
+                let mut steps = steps
+                    .iter()
+                    .map(|&Step(mut pair)| {
+                        pair.sort_unstable();
+                        pair
+                    })
+                    .collect::<Vec<_>>();
+                steps.sort_unstable();
+                steps
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(sets.len(), 20);
+    }
+
+    #[test]
+    fn counting_gives_up_at_the_timeout() {
+        // The search does not finish on this board within a minute.
+        let position = GamePosition::from(Generator::new(10).generate());
+
+        let start = Instant::now();
+        let solutions = position.solutions(10, Duration::from_millis(200));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(solutions.solutions.is_empty());
+        assert!(!solutions.complete);
+    }
+}
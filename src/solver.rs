@@ -1,54 +1,391 @@
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-use crate::board::{Board, Step};
+use crate::{
+    board::{Board, Step},
+    difficulty::{self, Difficulty},
+    hints::{self, Hint},
+    parallel,
+    pruning::Pruning,
+    rules::RuleSet,
+    search::{Budget, Search, SearchStatus},
+    solutions::{self, Solutions},
+};
 
+/// How often the progress callback of [`GamePosition::solve_with_progress`] is called.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A [`Board`] that is known to be a freshly dealt game under its [`RuleSet`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InitialBoard {
+    board: Board,
+    rules: RuleSet,
+}
+
+/// A [`Board`] that is known to be [consistent](RuleSet::is_consistent) under its [`RuleSet`],
+/// e.g. a game in progress.
+///
+/// Every [`InitialBoard`] is also a valid game position.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) struct InitialBoard(Board);
+pub struct GamePosition {
+    board: Board,
+    rules: RuleSet,
+}
 
-pub(crate) enum SolveResult {
+/// The outcome of [`GamePosition::solve`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveResult {
+    /// Every possible sequence of steps was tried without clearing the board.
     Unsolvable,
+    /// The search took longer than the given timeout.
     Timeout,
+    /// The steps that clear the board, in order.
     Solution(Vec<Step>),
 }
 
 impl InitialBoard {
-    pub(crate) fn new(board: Board) -> Option<Self> {
-        board.is_valid_initial_state().then_some(Self(board))
+    /// Returns the board if it [is a valid initial state](Board::is_valid_initial_state).
+    pub fn new(board: Board) -> Option<Self> {
+        Self::with_rules(board, RuleSet::STANDARD)
     }
 
-    pub(crate) fn solve(self, timeout: Duration) -> SolveResult {
-        let mut valid_steps = Vec::<Vec<Step>>::new();
-        let mut final_steps = Vec::<Step>::new();
-        let mut board = self.0;
-        let start = Instant::now();
+    /// Returns the board if it [is a valid initial state](RuleSet::is_valid_initial_state) under
+    /// the given rules, which are then used for solving it.
+    pub fn with_rules(board: Board, rules: RuleSet) -> Option<Self> {
+        rules
+            .is_valid_initial_state(board)
+            .then_some(Self { board, rules })
+    }
 
-        valid_steps.push(board.valid_steps());
+    /// The board as dealt.
+    pub fn board(self) -> Board {
+        self.board
+    }
 
-        loop {
-            let Some(step) = valid_steps.last_mut().unwrap().pop() else {
-                if valid_steps.pop().is_none() {
-                    break SolveResult::Unsolvable;
-                }
+    /// The rules the board is solved under.
+    pub fn rules(self) -> RuleSet {
+        self.rules
+    }
+
+    /// See [`GamePosition::solve`].
+    pub fn solve(self, timeout: Duration) -> SolveResult {
+        GamePosition::from(self).solve(timeout)
+    }
 
-                let step = final_steps.pop().unwrap();
-                board.set(step.0[0], self.0.get(step.0[0]));
-                board.set(step.0[1], self.0.get(step.0[1]));
-                continue;
-            };
+    /// See [`GamePosition::solve_with_stats`].
+    pub fn solve_with_stats(self, timeout: Duration) -> (SolveResult, SolveStats) {
+        GamePosition::from(self).solve_with_stats(timeout)
+    }
 
-            final_steps.push(step);
-            board.set(step.0[0], None);
-            board.set(step.0[1], None);
+    /// See [`GamePosition::solve_with_progress`].
+    pub fn solve_with_progress(
+        self,
+        timeout: Duration,
+        progress: impl FnMut(&SolveStats),
+    ) -> (SolveResult, SolveStats) {
+        GamePosition::from(self).solve_with_progress(timeout, progress)
+    }
 
-            if board.is_solved() {
-                break SolveResult::Solution(final_steps);
-            }
+    /// See [`GamePosition::solve_with`].
+    pub fn solve_with(self, options: SolveOptions) -> (SolveResult, SolveStats) {
+        GamePosition::from(self).solve_with(options)
+    }
+}
+
+impl GamePosition {
+    /// Returns the board if it [is consistent](Board::is_consistent).
+    pub fn new(board: Board) -> Option<Self> {
+        Self::with_rules(board, RuleSet::STANDARD)
+    }
+
+    /// Returns the board if it [is consistent](RuleSet::is_consistent) under the given rules,
+    /// which are then used for solving it.
+    pub fn with_rules(board: Board, rules: RuleSet) -> Option<Self> {
+        rules.is_consistent(board).then_some(Self { board, rules })
+    }
+
+    /// The marbles still on the board.
+    pub fn board(self) -> Board {
+        self.board
+    }
+
+    /// The rules the board is solved under.
+    pub fn rules(self) -> RuleSet {
+        self.rules
+    }
+
+    /// Searches for a sequence of steps that clears the board, giving up after `timeout`.
+    ///
+    /// Use a [`Search`] to keep the search state around when running out of time, or to stop it
+    /// from another thread.
+    pub fn solve(self, timeout: Duration) -> SolveResult {
+        self.solve_with_stats(timeout).0
+    }
+
+    /// Same as [`GamePosition::solve`], but also returns statistics about the search.
+    pub fn solve_with_stats(self, timeout: Duration) -> (SolveResult, SolveStats) {
+        self.solve_with_progress(timeout, |_| {})
+    }
+
+    /// Same as [`GamePosition::solve_with_stats`], but also periodically calls `progress` with the
+    /// statistics so far.
+    pub fn solve_with_progress(
+        self,
+        timeout: Duration,
+        progress: impl FnMut(&SolveStats),
+    ) -> (SolveResult, SolveStats) {
+        run(Search::new(self), timeout, progress)
+    }
+
+    /// Same as [`GamePosition::solve_with_stats`], but configured by `options`.
+    pub fn solve_with(self, options: SolveOptions) -> (SolveResult, SolveStats) {
+        if options.threads <= 1 {
+            run(
+                Search::new(self).pruning(options.pruning),
+                options.timeout,
+                |_| {},
+            )
+        } else {
+            parallel::solve(self, options)
+        }
+    }
+
+    /// Checks for each step that can be made right now whether the board can still be cleared
+    /// afterwards, giving up on a step after `options.timeout`.
+    pub fn hints(self, options: SolveOptions) -> Vec<Hint> {
+        hints::hints(self, options)
+    }
 
-            valid_steps.push(board.valid_steps());
+    /// Estimates how hard the position is, solving it with `options` along the way.
+    ///
+    /// Besides the solve itself, this checks every step available right now and every step along
+    /// the solution, spending up to a tenth of a second on each.
+    pub fn difficulty(self, options: SolveOptions) -> Difficulty {
+        difficulty::rate(self, options)
+    }
 
-            if start.elapsed() > timeout {
-                break SolveResult::Timeout;
+    /// Finds up to `limit` distinct solutions, where solutions that remove the same pairs in a
+    /// different order count as one, giving up after `timeout`.
+    pub fn solutions(self, limit: usize, timeout: Duration) -> Solutions {
+        solutions::enumerate(self, limit, timeout)
+    }
+}
+
+impl From<InitialBoard> for GamePosition {
+    fn from(InitialBoard { board, rules }: InitialBoard) -> Self {
+        Self { board, rules }
+    }
+}
+
+/// Runs a search until it is finished or `timeout` has passed.
+fn run(
+    mut search: Search,
+    timeout: Duration,
+    mut progress: impl FnMut(&SolveStats),
+) -> (SolveResult, SolveStats) {
+    let result = loop {
+        let remaining = timeout.saturating_sub(search.stats().elapsed);
+        match search.run(Budget::Time(remaining.min(PROGRESS_INTERVAL))) {
+            SearchStatus::Solved(steps) => break SolveResult::Solution(steps),
+            SearchStatus::Unsolvable => break SolveResult::Unsolvable,
+            SearchStatus::Paused | SearchStatus::Cancelled => {
+                if search.stats().elapsed >= timeout {
+                    break SolveResult::Timeout;
+                }
+                progress(search.stats());
             }
         }
+    };
+    (result, search.stats().clone())
+}
+
+/// A strategy for searching a solution, e.g. to compare them with each other.
+///
+/// [`GamePosition::solve_with`] always uses [`DepthFirst`].
+pub trait Solver {
+    /// Searches for a sequence of steps that clears the position, configured by `options`.
+    fn solve(&self, position: GamePosition, options: SolveOptions) -> (SolveResult, SolveStats);
+}
+
+/// The regular depth-first search, which tries the steps of each position in the order of
+/// [`RuleSet::valid_steps`] and backtracks when running out of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DepthFirst;
+
+impl Solver for DepthFirst {
+    fn solve(&self, position: GamePosition, options: SolveOptions) -> (SolveResult, SolveStats) {
+        position.solve_with(options)
+    }
+}
+
+/// How to search for a solution with [`GamePosition::solve_with`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SolveOptions {
+    /// How long to search before giving up.
+    pub timeout: Duration,
+    /// The number of threads to search with.
+    ///
+    /// Which solution is found first depends on the timing of the threads, so it may differ
+    /// between runs. A single thread searches exactly like [`GamePosition::solve`].
+    pub threads: usize,
+    /// Which feasibility checks to apply after each step.
+    pub pruning: Pruning,
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            threads: 1,
+            pruning: Pruning::default(),
+        }
+    }
+}
+
+/// Statistics collected while solving.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolveStats {
+    /// The number of positions for which valid steps were generated.
+    pub nodes: u64,
+    /// How often a step had to be undone, because it did not lead to a solution.
+    pub backtracks: u64,
+    /// The number of positions that were skipped because they failed a [feasibility check](Pruning).
+    pub pruned: u64,
+    /// The largest number of steps applied at the same time.
+    pub max_depth: usize,
+    /// The branching at each depth, i.e. number of steps already applied.
+    pub branching: Vec<Branching>,
+    /// How long the search took so far.
+    pub elapsed: Duration,
+}
+
+impl SolveStats {
+    /// Adds the statistics of a search of another part of the same tree, keeping `elapsed`.
+    pub(crate) fn merge(&mut self, other: &SolveStats) {
+        self.nodes += other.nodes;
+        self.backtracks += other.backtracks;
+        self.pruned += other.pruned;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        if self.branching.len() < other.branching.len() {
+            self.branching
+                .resize(other.branching.len(), Branching::default());
+        }
+        for (branching, other) in self.branching.iter_mut().zip(&other.branching) {
+            branching.nodes += other.nodes;
+            branching.steps += other.steps;
+        }
+    }
+
+    pub(crate) fn expanded(&mut self, depth: usize, steps: usize) {
+        self.nodes += 1;
+        if self.branching.len() <= depth {
+            self.branching.resize(depth + 1, Branching::default());
+        }
+        self.branching[depth].nodes += 1;
+        self.branching[depth].steps += steps as u64;
+    }
+}
+
+/// How many steps were available at a given depth of the search.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Branching {
+    /// The number of positions expanded at this depth.
+    pub nodes: u64,
+    /// The total number of valid steps of all those positions.
+    pub steps: u64,
+}
+
+impl Branching {
+    /// The average number of valid steps per position.
+    pub fn factor(self) -> f64 {
+        self.steps as f64 / self.nodes as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use crate::{element::Element, generator::Generator};
+
+    use super::*;
+
+    fn solve_without_pruning(elements: &[(i8, i8, Element)]) -> (SolveResult, SolveStats) {
+        GamePosition::new(Board::with_elements(elements))
+            .unwrap()
+            .solve_with(SolveOptions {
+                pruning: Pruning::NONE,
+                ..SolveOptions::default()
+            })
+    }
+
+    #[test]
+    fn stats_count_nodes_and_backtracks() {
+        // The two fires are tried first, leaving salt and water.
+        let (result, stats) = solve_without_pruning(&[
+            (0, 0, Element::Fire),
+            (0, 2, Element::Fire),
+            (2, 2, Element::Salt),
+            (-2, -2, Element::Water),
+        ]);
+        assert!(matches!(result, SolveResult::Solution(_)));
+        assert_eq!((stats.nodes, stats.backtracks, stats.max_depth), (2, 0, 2));
+        assert_eq!(stats.branching[0], Branching { nodes: 1, steps: 4 });
+
+        // Whichever element the salt is paired with, the other two are left over.
+        let (result, stats) = solve_without_pruning(&[
+            (0, 0, Element::Fire),
+            (0, 2, Element::Water),
+            (2, 2, Element::Salt),
+            (-2, -2, Element::Earth),
+        ]);
+        assert_eq!(result, SolveResult::Unsolvable);
+        assert_eq!((stats.nodes, stats.backtracks, stats.max_depth), (4, 3, 1));
+    }
+
+    #[test]
+    fn merged_stats_add_up() {
+        let mut stats = SolveStats::default();
+        stats.expanded(0, 3);
+        stats.expanded(1, 2);
+        stats.max_depth = 2;
+        let mut other = SolveStats::default();
+        other.expanded(0, 1);
+        other.expanded(1, 4);
+        other.expanded(2, 1);
+        other.backtracks = 5;
+        other.max_depth = 3;
+
+        stats.merge(&other);
+        assert_eq!((stats.nodes, stats.backtracks, stats.max_depth), (5, 5, 3));
+        assert_eq!(
+            stats.branching,
+            [
+                Branching { nodes: 2, steps: 4 },
+                Branching { nodes: 2, steps: 6 },
+                Branching { nodes: 1, steps: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn progress_is_reported_during_long_searches() {
+        // Without any way to remove fire, the search has to try every order of the other steps.
+        let rules = RuleSet::STANDARD
+            .pair(Element::Fire, Element::Fire, false)
+            .pair(Element::Fire, Element::Salt, false);
+        let board = Generator::new(7).generate().board();
+        let position = GamePosition::with_rules(board, rules).unwrap();
+
+        let start = Instant::now();
+        let mut reported = Vec::new();
+        let (result, stats) = position
+            .solve_with_progress(3 * PROGRESS_INTERVAL + PROGRESS_INTERVAL / 2, |stats| {
+                reported.push(stats.nodes)
+            });
+        assert_eq!(result, SolveResult::Timeout);
+        assert!(start.elapsed() >= 3 * PROGRESS_INTERVAL);
+        assert!(reported.len() >= 3, "{reported:?}");
+        assert!(reported.is_sorted());
+        assert!(reported.last() <= Some(&stats.nodes));
     }
 }
@@ -0,0 +1,203 @@
+//! The 12 rotations and reflections that map the hexagonal playing field onto itself.
+//!
+//! Whether a position is free only depends on its neighbors, which stay neighbors under each of
+//! these symmetries, so a solution of a board is turned into a solution of its image by
+//! transforming each step the same way.
+
+use crate::{
+    board::{Board, Step},
+    index::{SigCoord, SigIndex},
+};
+
+/// A rotation around the center, optionally preceded by a reflection across the diagonal through
+/// the top right and bottom left corners.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symmetry {
+    reflected: bool,
+    /// Clockwise rotation in multiples of 60 degrees, in the range `0..6`.
+    rotation: u8,
+}
+
+impl Symmetry {
+    /// Leaves everything in place.
+    pub const IDENTITY: Self = Self::rotation(0);
+
+    /// All 12 symmetries, starting with the 6 rotations.
+    pub const ALL: [Self; 12] = {
+        let mut all = [Self::IDENTITY; 12];
+        let mut i = 0;
+        while i < 12 {
+            all[i] = Self {
+                reflected: i >= 6,
+                rotation: (i % 6) as u8,
+            };
+            i += 1;
+        }
+        all
+    };
+
+    /// Rotates clockwise around the center by `steps` times 60 degrees.
+    pub const fn rotation(steps: u8) -> Self {
+        Self {
+            reflected: false,
+            rotation: steps % 6,
+        }
+    }
+
+    /// Swaps rows and columns, which mirrors the field across the diagonal through the top right
+    /// and bottom left corners.
+    pub const fn reflection() -> Self {
+        Self {
+            reflected: true,
+            rotation: 0,
+        }
+    }
+
+    /// Whether this symmetry mirrors the field, i.e. turns clockwise order into counterclockwise.
+    pub fn is_reflection(self) -> bool {
+        self.reflected
+    }
+
+    /// The symmetry that undoes this one.
+    pub fn inverse(self) -> Self {
+        // Reflecting, rotating and reflecting again rotates the other way.
+        Self {
+            reflected: self.reflected,
+            rotation: if self.reflected {
+                self.rotation
+            } else {
+                (6 - self.rotation) % 6
+            },
+        }
+    }
+
+    /// The symmetry that applies this one first and then `other`.
+    pub fn then(self, other: Self) -> Self {
+        let rotation = if other.reflected {
+            6 - self.rotation
+        } else {
+            self.rotation
+        };
+        Self {
+            reflected: self.reflected != other.reflected,
+            rotation: (rotation + other.rotation) % 6,
+        }
+    }
+}
+
+impl SigCoord {
+    /// Maps the coordinate to its image under the symmetry.
+    pub fn transform(self, symmetry: Symmetry) -> Self {
+        let (mut row, mut col) = (self.row(), self.col());
+        if symmetry.reflected {
+            (row, col) = (col, row);
+        }
+        for _ in 0..symmetry.rotation {
+            (row, col) = (row - col, row);
+        }
+        SigCoord::new(row, col).expect("symmetries keep coordinates on the playing field")
+    }
+}
+
+impl SigIndex {
+    /// Maps the index to its image under the symmetry.
+    pub fn transform(self, symmetry: Symmetry) -> Self {
+        SigCoord::from(self).transform(symmetry).into()
+    }
+}
+
+impl Step {
+    /// Maps both positions of the step to their images under the symmetry.
+    pub fn transform(self, symmetry: Symmetry) -> Self {
+        Step(self.0.map(|index| index.transform(symmetry)))
+    }
+}
+
+impl Board {
+    /// Moves every element to the image of its position under the symmetry.
+    pub fn transform(self, symmetry: Symmetry) -> Self {
+        let mut board = Board::new();
+        for index in SigIndex::all() {
+            board.set(index.transform(symmetry), self.get(index));
+        }
+        board
+    }
+
+    /// The smallest of the 12 images of this board, along with the symmetry that maps this board
+    /// to it.
+    ///
+    /// Boards that are symmetric images of each other have the same canonical form, so e.g. a
+    /// solution of the canonical form can be mapped back with [`Symmetry::inverse`].
+    pub fn canonical(self) -> (Board, Symmetry) {
+        Symmetry::ALL
+            .into_iter()
+            .map(|symmetry| (self.transform(symmetry), symmetry))
+            .min()
+            .expect("there is always at least one symmetry")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, time::Duration};
+
+    use crate::{generator::Generator, solver::SolveResult};
+
+    use super::*;
+
+    #[test]
+    fn group_laws() {
+        let all = Symmetry::ALL.into_iter().collect::<HashSet<_>>();
+        assert_eq!(all.len(), 12);
+
+        let coords = SigIndex::all().map(SigCoord::from).collect::<Vec<_>>();
+        for a in Symmetry::ALL {
+            for &coord in &coords {
+                assert_eq!(coord.transform(a).transform(a.inverse()), coord);
+            }
+            for b in Symmetry::ALL {
+                for &coord in &coords {
+                    assert_eq!(coord.transform(a).transform(b), coord.transform(a.then(b)));
+                }
+            }
+        }
+        for coord in coords {
+            assert_eq!(coord.transform(Symmetry::rotation(6)), coord);
+            assert_eq!(coord.transform(Symmetry::IDENTITY), coord);
+        }
+    }
+
+    #[test]
+    fn solutions_map_to_images() {
+        let board = Generator::new(3).solvable(true).generate();
+        let SolveResult::Solution(steps) = board.solve(Duration::from_secs(10)) else {
+            panic!("generated board should be solvable");
+        };
+        for symmetry in Symmetry::ALL {
+            let mut image = board.board().transform(symmetry);
+            for &step in &steps {
+                let step = step.transform(symmetry);
+                let reversed = Step([step.0[1], step.0[0]]);
+                let valid_steps = image.valid_steps();
+                assert!(valid_steps.contains(&step) || valid_steps.contains(&reversed));
+                image.remove(step);
+            }
+            assert!(image.is_solved());
+        }
+    }
+
+    #[test]
+    fn canonical_form_is_shared_by_images() {
+        let board = Generator::new(1).generate().board();
+        let (canonical, symmetry) = board.canonical();
+        assert_eq!(board.transform(symmetry), canonical);
+        for other in Symmetry::ALL {
+            let (image_canonical, image_symmetry) = board.transform(other).canonical();
+            assert_eq!(image_canonical, canonical);
+            assert_eq!(
+                canonical.transform(image_symmetry.inverse()),
+                board.transform(other)
+            );
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Checking that a list of steps clears a board.
+//!
+//! Each step is replayed with [`Board::apply`], which checks the rules directly on the board
+//! instead of relying on the move generator used by the solver, so a bug in one is caught by the
+//! other.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{
+    board::{Board, Step, StepError},
+    index::SigIndex,
+};
+
+/// Replays the steps on the board and checks that all elements are removed in the end.
+pub fn verify(board: Board, steps: &[Step]) -> Result<(), VerifyError> {
+    let board = steps
+        .iter()
+        .enumerate()
+        .try_fold(board, |board, (number, &step)| {
+            board.apply(step).map_err(|error| VerifyError::IllegalStep {
+                number,
+                step,
+                error,
+            })
+        })?;
+
+    let leftover = SigIndex::all()
+        .filter(|&index| board.get(index).is_some())
+        .count();
+    if leftover == 0 {
+        Ok(())
+    } else {
+        Err(VerifyError::Leftover(leftover))
+    }
+}
+
+/// Why a list of steps does not clear a board, as found by [`verify`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The first step that cannot be made, counting from 0.
+    IllegalStep {
+        /// The number of the step.
+        number: usize,
+        /// The step itself.
+        step: Step,
+        /// Why the step cannot be made.
+        error: StepError,
+    },
+    /// The number of elements left on the board after all steps.
+    Leftover(usize),
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IllegalStep {
+                number,
+                step,
+                error,
+            } => write!(f, "step {} ({step}) is illegal: {error}", number + 1),
+            Self::Leftover(count) => write!(f, "{count} elements are left on the board"),
+        }
+    }
+}
+
+impl Error for VerifyError {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{generator::Generator, solver::SolveResult};
+
+    use super::*;
+
+    #[test]
+    fn solutions_are_verified() {
+        let board = Generator::new(7).solvable(true).generate();
+        let SolveResult::Solution(mut steps) = board.solve(Duration::from_secs(60)) else {
+            panic!("generated board should be solvable");
+        };
+        assert_eq!(verify(board.board(), &steps), Ok(()));
+
+        let last = steps.pop().unwrap();
+        let removed = if last.0[0] == last.0[1] { 1 } else { 2 };
+        assert_eq!(
+            verify(board.board(), &steps),
+            Err(VerifyError::Leftover(removed))
+        );
+
+        steps.insert(0, last);
+        assert!(matches!(
+            verify(board.board(), &steps),
+            Err(VerifyError::IllegalStep { number: 0, .. })
+        ));
+    }
+}